@@ -1,6 +1,6 @@
 import { ref, computed, onMounted, type ComputedRef } from "vue";
 import api from "@/utils/api";
-import { decodeBase64urlBytes, djb2Hash } from "@/utils/encoding";
+import { decodeBase64urlBytes, djb2Hash, encodeBase64urlBytes } from "@/utils/encoding";
 import useEnvId from "./useEnvId";
 
 type PushState =
@@ -24,6 +24,13 @@ const detectInitialPermission = (): NotificationPermission | "unsupported" => {
 
 const endpointId = (endpoint: string): string => djb2Hash(endpoint).toString(16);
 
+// The VAPID public key this subscription was actually created with, which may not be the one we
+// would subscribe with today if the key has been rotated since.
+const subscriptionVapidKey = (subscription: PushSubscription): string | undefined => {
+  const key = subscription.options.applicationServerKey;
+  return key ? encodeBase64urlBytes(new Uint8Array(key)) : undefined;
+};
+
 // `Notification.permission` shared across all composable consumers — the
 // state is global (per-origin), so there's no reason to recompute it per
 // component instance.
@@ -51,7 +58,11 @@ const usePushNotifications = (): {
       if (existing && envId.value) {
         const key = `subscription:${envId.value}:${endpointId(existing.endpoint)}`;
         if (localStorage.getItem(key) === null) {
-          const result = await api.postSubscription(envId.value, existing.toJSON());
+          const result = await api.postSubscription(
+            envId.value,
+            existing.toJSON(),
+            subscriptionVapidKey(existing),
+          );
           if (result.ok) {
             localStorage.setItem(key, "true");
           }
@@ -90,7 +101,11 @@ const usePushNotifications = (): {
         userVisibleOnly: true,
         applicationServerKey: decodeBase64urlBytes(publicKey),
       });
-      const result = await api.postSubscription(envId.value, subscription.toJSON());
+      const result = await api.postSubscription(
+        envId.value,
+        subscription.toJSON(),
+        subscriptionVapidKey(subscription),
+      );
       if (result.ok) {
         const key = `subscription:${envId.value}:${endpointId(subscription.endpoint)}`;
         localStorage.setItem(key, "true");
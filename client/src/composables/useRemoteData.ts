@@ -6,6 +6,7 @@ import {
   toRef,
   provide,
   onMounted,
+  onUnmounted,
   inject,
   ref,
   computed,
@@ -18,6 +19,7 @@ import api, {
   type Config,
   type Event,
   type Info,
+  type LiveChange,
   type Page,
 } from "@/utils/api";
 import { envContext } from "@/context";
@@ -685,6 +687,40 @@ export const provideRemoteData = () => {
       }
     });
   }
+
+  // The server tells us when a section changes, so we can refetch it without
+  // waiting for the next reload. We skip the refetch when we already have the
+  // version it's telling us about, or when the current route doesn't use it;
+  // it'll be fetched when the user navigates to a route that does.
+  //
+  // Live ETags are only for unfiltered sections. That's what every data source
+  // here fetches, so the ETags we store are comparable. A data source that
+  // fetches with filters would need to skip this comparison.
+  const route = useRoute();
+  const envId = useEnvId();
+  let closeLive: (() => void) | undefined;
+
+  const onLiveChange = ({ section, etag }: LiveChange) => {
+    if (section === "files") return;
+    if (!matchesRoute(FETCH_POLICIES[section], route.name as string | undefined)) return;
+    if (getItem(section)?.etag === etag) return;
+    void remoteData.reload[section]();
+  };
+
+  onMounted(() => {
+    watch(
+      envId,
+      (id) => {
+        closeLive?.();
+        closeLive = id ? api.subscribeLive(id, onLiveChange) : undefined;
+      },
+      { immediate: true },
+    );
+  });
+
+  onUnmounted(() => {
+    closeLive?.();
+  });
 };
 
 const injectRemoteData = () => {
@@ -79,6 +79,24 @@ interface PushPayload {
   body: string;
   url: string;
   icon: string | null;
+  // Older servers don't send these.
+  tag?: string | null;
+  renotify?: boolean;
+  image?: string | null;
+  badge?: string | null;
+  actions?: Array<PushAction>;
+  timestamp?: number | null;
+}
+
+interface PushAction {
+  action: string;
+  title: string;
+  url: string;
+}
+
+interface NotificationData {
+  url: string;
+  actions: Record<string, string>;
 }
 
 self.addEventListener("push", (event) => {
@@ -93,11 +111,24 @@ self.addEventListener("push", (event) => {
 
   event.waitUntil(
     (async () => {
+      const actions = payload.actions ?? [];
+      const data: NotificationData = {
+        url: payload.url,
+        actions: Object.fromEntries(actions.map(({ action, url }) => [action, url])),
+      };
+
+      // Some of these options aren't in TypeScript's `NotificationOptions` yet.
       await self.registration.showNotification(payload.title, {
         body: payload.body,
         icon: payload.icon ?? "/icons/icon-padded.png",
-        data: { url: payload.url },
-      });
+        badge: payload.badge ?? "/icons/icon.png",
+        tag: payload.tag ?? undefined,
+        renotify: payload.tag ? (payload.renotify ?? false) : false,
+        image: payload.image ?? undefined,
+        timestamp: payload.timestamp ?? undefined,
+        actions: actions.map(({ action, title }) => ({ action, title })),
+        data,
+      } as NotificationOptions);
 
       // Nudge any open clients to refetch the announcement list.
       const clients = await self.clients.matchAll({
@@ -113,7 +144,8 @@ self.addEventListener("push", (event) => {
 
 self.addEventListener("notificationclick", (event) => {
   event.notification.close();
-  const targetPath = (event.notification.data as { url?: string } | undefined)?.url ?? "/";
+  const data = event.notification.data as Partial<NotificationData> | undefined;
+  const targetPath = (event.action && data?.actions?.[event.action]) || data?.url || "/";
   const targetUrl = new URL(targetPath, self.location.origin).toString();
 
   event.waitUntil(
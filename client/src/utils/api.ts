@@ -371,16 +371,19 @@ const getAlias = async (aliasId: string): Promise<ApiResult<string>> => {
   return { ok: true, value: rawConfig.env_id };
 };
 
+// The VAPID public key is the `applicationServerKey` the subscription was created with, which the
+// server needs to know to send to it.
 const postSubscription = async (
   envId: string,
   subscription: PushSubscriptionJSON,
+  vapidPublicKey: string | undefined,
 ): Promise<ApiResult<void>> => {
   const response = await fetch(
     `https://${import.meta.env.VITE_API_HOST as string}/apps/${envId}/subscription`,
     {
       method: "POST",
       headers: { "Content-Type": "application/json" },
-      body: JSON.stringify(subscription),
+      body: JSON.stringify({ ...subscription, vapid_public_key: vapidPublicKey }),
     },
   );
 
@@ -408,6 +411,27 @@ const deleteSubscription = async (envId: string, endpoint: string): Promise<ApiR
   return { ok: true, value: undefined };
 };
 
+// A section of the app's data the server tells live clients has changed. The ETag matches the one
+// the section's endpoint returns without any query params, so it can't be compared with the ETag of
+// a filtered or projected response.
+export interface LiveChange {
+  section: "events" | "info" | "pages" | "announcements" | "files";
+  etag: string;
+}
+
+// Open the live update stream for an environment. Returns a function that closes it.
+const subscribeLive = (envId: string, onChange: (change: LiveChange) => void): (() => void) => {
+  const source = new EventSource(
+    `https://${import.meta.env.VITE_API_HOST as string}/apps/${envId}/live`,
+  );
+
+  source.addEventListener("changed", (event: MessageEvent<string>) => {
+    onChange(JSON.parse(event.data) as LiveChange);
+  });
+
+  return () => source.close();
+};
+
 export default {
   getEvents,
   getInfo,
@@ -417,4 +441,5 @@ export default {
   getAlias,
   postSubscription,
   deleteSubscription,
+  subscribeLive,
 };
@@ -19,8 +19,7 @@ export const decodeBase64urlBytes = (encoded: string): Uint8Array => {
   });
 };
 
-export const encodeBase64url = (text: string): string => {
-  const bytes = new TextEncoder().encode(text);
+export const encodeBase64urlBytes = (bytes: Uint8Array): string => {
   // @ts-expect-error This browser API is still very new.
   return bytes.toBase64({
     alphabet: "base64url",
@@ -28,6 +27,10 @@ export const encodeBase64url = (text: string): string => {
   });
 };
 
+export const encodeBase64url = (text: string): string => {
+  return encodeBase64urlBytes(new TextEncoder().encode(text));
+};
+
 export const decodeBase64url = (encoded: string): string => {
   // @ts-expect-error This browser API is still very new.
   const bytes = Uint8Array.fromBase64(encoded, {
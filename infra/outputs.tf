@@ -63,7 +63,7 @@ output "noco_secrets" {
 
 output "worker_admin_api_tokens" {
   value = {
-    for stage, _ in local.stages : stage => random_bytes.worker_admin_api_token[stage].base64
+    for stage, _ in local.stages : stage => random_bytes.worker_admin_api_token["${stage}-${local.admin_api_token_counter}"].base64
   }
   sensitive = true
 }
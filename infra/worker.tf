@@ -1,33 +1,101 @@
 locals {
-  # Increment this to roll the worker admin API token.
-  admin_api_token_counter = 1
+  # Increment this to roll the worker admin API token. To roll it without
+  # breaking tools that are still using the old token, set the not-after time
+  # to an RFC 3339 timestamp when the old token should stop working. Set it
+  # back to `null` once that time has passed.
+  admin_api_token_counter            = 1
+  admin_api_token_previous_not_after = null
 
   # Increment this to roll the NocoDB → server webhook auth token. Rolling
   # this also requires re-running the migration that registers the
-  # Announcements webhook so NocoDB starts sending the new value.
-  noco_webhook_token_counter = 1
-}
+  # Announcements webhook so NocoDB starts sending the new value. Set the
+  # not-after time so NocoDB can keep sending the old value until then.
+  noco_webhook_token_counter            = 1
+  noco_webhook_token_previous_not_after = null
+
+  # We keep the current and previous generation of each token, keyed by stage
+  # and counter, so the previous token survives incrementing the counter. The
+  # first generation has no previous one.
+  admin_api_token_generations = {
+    for pair in setproduct(
+      keys(local.stages),
+      local.admin_api_token_counter > 1 ? [local.admin_api_token_counter - 1, local.admin_api_token_counter] : [local.admin_api_token_counter],
+    ) :
+    "${pair[0]}-${pair[1]}" => pair[0]
+  }
 
-resource "random_bytes" "worker_admin_api_token" {
-  for_each = local.stages
+  noco_webhook_token_generations = {
+    for pair in setproduct(
+      keys(local.stages),
+      local.noco_webhook_token_counter > 1 ? [local.noco_webhook_token_counter - 1, local.noco_webhook_token_counter] : [local.noco_webhook_token_counter],
+    ) :
+    "${pair[0]}-${pair[1]}" => pair[0]
+  }
+
+  # The worker accepts any unexpired token in these sets; the first one is
+  # the current token.
+  admin_api_token_sets = {
+    for stage, _ in local.stages : stage => concat(
+      [{ token = random_bytes.worker_admin_api_token["${stage}-${local.admin_api_token_counter}"].base64, not_after = null }],
+      local.admin_api_token_previous_not_after == null ? [] : [{
+        token     = random_bytes.worker_admin_api_token["${stage}-${local.admin_api_token_counter - 1}"].base64,
+        not_after = local.admin_api_token_previous_not_after,
+      }],
+    )
+  }
 
-  keepers = {
-    counter = local.admin_api_token_counter
+  noco_webhook_token_sets = {
+    for stage, _ in local.stages : stage => concat(
+      [{ token = random_bytes.noco_webhook_token["${stage}-${local.noco_webhook_token_counter}"].base64, not_after = null }],
+      local.noco_webhook_token_previous_not_after == null ? [] : [{
+        token     = random_bytes.noco_webhook_token["${stage}-${local.noco_webhook_token_counter - 1}"].base64,
+        not_after = local.noco_webhook_token_previous_not_after,
+      }],
+    )
   }
+}
+
+resource "random_bytes" "worker_admin_api_token" {
+  for_each = local.admin_api_token_generations
 
   length = 32
 }
 
-resource "random_bytes" "noco_webhook_token" {
+moved {
+  from = random_bytes.worker_admin_api_token["prod"]
+  to   = random_bytes.worker_admin_api_token["prod-1"]
+}
+
+moved {
+  from = random_bytes.worker_admin_api_token["test"]
+  to   = random_bytes.worker_admin_api_token["test-1"]
+}
+
+# This encrypts sensitive values the worker stores at rest in KV. There is
+# deliberately no counter to roll it; changing it makes every encrypted value
+# unreadable.
+resource "random_bytes" "config_encryption_key" {
   for_each = local.stages
 
-  keepers = {
-    counter = local.noco_webhook_token_counter
-  }
+  length = 32
+}
+
+resource "random_bytes" "noco_webhook_token" {
+  for_each = local.noco_webhook_token_generations
 
   length = 32
 }
 
+moved {
+  from = random_bytes.noco_webhook_token["prod"]
+  to   = random_bytes.noco_webhook_token["prod-1"]
+}
+
+moved {
+  from = random_bytes.noco_webhook_token["test"]
+  to   = random_bytes.noco_webhook_token["test-1"]
+}
+
 resource "cloudflare_workers_secret" "neon_api_token" {
   for_each = local.stages
 
@@ -63,7 +131,16 @@ resource "cloudflare_workers_secret" "admin_api_token" {
   account_id  = var.cloudflare_account_id
   name        = "ADMIN_API_TOKEN"
   script_name = "sparklefish-server-${each.key}"
-  secret_text = random_bytes.worker_admin_api_token[each.key].base64
+  secret_text = jsonencode(local.admin_api_token_sets[each.key])
+}
+
+resource "cloudflare_workers_secret" "config_encryption_key" {
+  for_each = local.stages
+
+  account_id  = var.cloudflare_account_id
+  name        = "CONFIG_ENCRYPTION_KEY"
+  script_name = "sparklefish-server-${each.key}"
+  secret_text = random_bytes.config_encryption_key[each.key].base64
 }
 
 resource "cloudflare_workers_secret" "noco_webhook_token" {
@@ -72,7 +149,7 @@ resource "cloudflare_workers_secret" "noco_webhook_token" {
   account_id  = var.cloudflare_account_id
   name        = "NOCO_WEBHOOK_TOKEN"
   script_name = "sparklefish-server-${each.key}"
-  secret_text = random_bytes.noco_webhook_token[each.key].base64
+  secret_text = jsonencode(local.noco_webhook_token_sets[each.key])
 }
 
 resource "cloudflare_workers_secret" "cloudflare_api_token" {
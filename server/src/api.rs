@@ -1,10 +1,25 @@
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-use crate::noco;
+use serde::{Deserialize, Serialize, Serializer, ser::SerializeMap};
+
+use crate::{
+    audit::AuditEntry,
+    auth::{OrganizerToken, Role},
+    env::{ConfigVersion, EnvName},
+    noco, push,
+};
 
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub key: String,
+    pub error: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -122,9 +137,146 @@ pub struct Event {
     pub tags: Vec<String>,
 }
 
+impl From<noco::Event> for Event {
+    fn from(event: noco::Event) -> Self {
+        Self {
+            id: event.id,
+            name: event.name,
+            summary: event.summary,
+            description: event.description,
+            start_time: event.start_time,
+            end_time: event.end_time,
+            location: event.location,
+            people: event.people,
+            category: event.category,
+            tags: event.tags,
+        }
+    }
+}
+
+// The fields of an event a client can ask for with `fields=`, in the order we return them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventField {
+    Id,
+    Name,
+    Summary,
+    Description,
+    StartTime,
+    EndTime,
+    Location,
+    People,
+    Category,
+    Tags,
+}
+
+impl EventField {
+    // The name of the field, as clients ask for it.
+    pub fn name(self) -> &'static str {
+        match self {
+            EventField::Id => "id",
+            EventField::Name => "name",
+            EventField::Summary => "summary",
+            EventField::Description => "description",
+            EventField::StartTime => "start_time",
+            EventField::EndTime => "end_time",
+            EventField::Location => "location",
+            EventField::People => "people",
+            EventField::Category => "category",
+            EventField::Tags => "tags",
+        }
+    }
+}
+
+// An event with only the fields the client asked for. If `fields` is `None`, this is the whole
+// event.
+#[derive(Debug, Clone)]
+pub struct ProjectedEvent {
+    pub event: Event,
+    pub fields: Option<Arc<[EventField]>>,
+}
+
+impl Serialize for ProjectedEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = &self.fields else {
+            return self.event.serialize(serializer);
+        };
+
+        let event = &self.event;
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+
+        for field in fields.iter() {
+            match field {
+                EventField::Id => map.serialize_entry("id", &event.id)?,
+                EventField::Name => map.serialize_entry("name", &event.name)?,
+                EventField::Summary => map.serialize_entry("summary", &event.summary)?,
+                EventField::Description => {
+                    map.serialize_entry("description", &event.description)?
+                }
+                EventField::StartTime => map.serialize_entry("start_time", &event.start_time)?,
+                EventField::EndTime => map.serialize_entry("end_time", &event.end_time)?,
+                EventField::Location => map.serialize_entry("location", &event.location)?,
+                EventField::People => map.serialize_entry("people", &event.people)?,
+                EventField::Category => map.serialize_entry("category", &event.category)?,
+                EventField::Tags => map.serialize_entry("tags", &event.tags)?,
+            }
+        }
+
+        map.end()
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct GetEventsResponse {
-    pub events: Vec<Event>,
+    pub events: Vec<ProjectedEvent>,
+}
+
+// Every field of every event, as the unfiltered events endpoint returns them.
+impl From<Vec<noco::Event>> for GetEventsResponse {
+    fn from(events: Vec<noco::Event>) -> Self {
+        Self {
+            events: events
+                .into_iter()
+                .map(|event| ProjectedEvent {
+                    event: Event::from(event),
+                    fields: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+// One section of the bundle. `value` is left out if the client already has this version of it.
+#[derive(Debug, Serialize)]
+pub struct BundleSection<T> {
+    pub hash: String,
+    pub stale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<T>,
+}
+
+// Everything the client app needs to start, in one request. Each section is the same as the
+// response from its own endpoint. The cached sections are plain JSON because they may come
+// straight from that endpoint's edge cache.
+#[derive(Debug, Serialize)]
+pub struct GetBundleResponse {
+    pub config: BundleSection<GetConfigResponse>,
+    pub info: BundleSection<serde_json::Value>,
+    pub events: BundleSection<serde_json::Value>,
+    pub pages: BundleSection<serde_json::Value>,
+    pub announcements: BundleSection<serde_json::Value>,
+    pub files: BundleSection<serde_json::Value>,
+}
+
+// The events that changed since a previous version of the schedule. If `full` is set, we didn't
+// know the previous version, so `changed` is every event and the client should replace what it
+// has.
+#[derive(Debug, Clone, Serialize)]
+pub struct GetEventChangesResponse {
+    pub version: String,
+    pub full: bool,
+    pub changed: Vec<Event>,
+    pub removed: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -141,6 +293,17 @@ pub struct File {
     pub signed_url: String,
 }
 
+impl From<noco::File> for File {
+    fn from(file: noco::File) -> Self {
+        Self {
+            id: file.id,
+            name: file.name,
+            media_type: file.media_type,
+            signed_url: file.signed_url,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct GetInfoResponse {
     pub env_name: String,
@@ -151,6 +314,26 @@ pub struct GetInfoResponse {
     pub files: Vec<File>,
 }
 
+impl GetInfoResponse {
+    pub fn new(env_name: String, info: noco::Info) -> Self {
+        Self {
+            env_name,
+            name: info.about.name,
+            description: info.about.description,
+            website_url: info.about.website_url,
+            links: info
+                .links
+                .into_iter()
+                .map(|link| Link {
+                    name: link.name,
+                    url: link.url,
+                })
+                .collect(),
+            files: info.about.files.into_iter().map(File::from).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Announcement {
     pub id: String,
@@ -166,11 +349,37 @@ pub struct GetAnnouncementsResponse {
     pub announcements: Vec<Announcement>,
 }
 
+impl From<Vec<noco::Announcement>> for GetAnnouncementsResponse {
+    fn from(announcements: Vec<noco::Announcement>) -> Self {
+        Self {
+            announcements: announcements
+                .into_iter()
+                .map(|announcement| Announcement {
+                    id: announcement.id,
+                    title: announcement.title,
+                    body: announcement.body,
+                    attachments: announcement.files.into_iter().map(File::from).collect(),
+                    created_at: announcement.created_at,
+                    updated_at: announcement.updated_at,
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct GetFilesResponse {
     pub files: Vec<File>,
 }
 
+impl From<Vec<noco::File>> for GetFilesResponse {
+    fn from(files: Vec<noco::File>) -> Self {
+        Self {
+            files: files.into_iter().map(File::from).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Page {
     pub id: String,
@@ -184,6 +393,80 @@ pub struct GetPagesResponse {
     pub pages: Vec<Page>,
 }
 
+impl From<Vec<noco::Page>> for GetPagesResponse {
+    fn from(pages: Vec<noco::Page>) -> Self {
+        Self {
+            pages: pages
+                .into_iter()
+                .map(|page| Page {
+                    id: page.id,
+                    title: page.title,
+                    body: page.body,
+                    files: page.files.into_iter().map(File::from).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+// Cached data, and the body the endpoint that serves it returns when the request has no filters.
+pub trait SectionBody {
+    type Body: Serialize;
+
+    fn section_body(&self, env_name: &EnvName) -> Self::Body;
+}
+
+impl SectionBody for [noco::Event] {
+    type Body = GetEventsResponse;
+
+    fn section_body(&self, _env_name: &EnvName) -> Self::Body {
+        GetEventsResponse::from(self.to_vec())
+    }
+}
+
+impl SectionBody for noco::Info {
+    type Body = GetInfoResponse;
+
+    fn section_body(&self, env_name: &EnvName) -> Self::Body {
+        GetInfoResponse::new(env_name.to_string(), self.clone())
+    }
+}
+
+impl SectionBody for [noco::Page] {
+    type Body = GetPagesResponse;
+
+    fn section_body(&self, _env_name: &EnvName) -> Self::Body {
+        GetPagesResponse::from(self.to_vec())
+    }
+}
+
+impl SectionBody for [noco::Announcement] {
+    type Body = GetAnnouncementsResponse;
+
+    fn section_body(&self, _env_name: &EnvName) -> Self::Body {
+        GetAnnouncementsResponse::from(self.to_vec())
+    }
+}
+
+impl SectionBody for [noco::File] {
+    type Body = GetFilesResponse;
+
+    fn section_body(&self, _env_name: &EnvName) -> Self::Body {
+        GetFilesResponse::from(self.to_vec())
+    }
+}
+
+impl<T> SectionBody for Vec<T>
+where
+    [T]: SectionBody,
+{
+    type Body = <[T] as SectionBody>::Body;
+
+    fn section_body(&self, env_name: &EnvName) -> Self::Body {
+        self.as_slice().section_body(env_name)
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct GetConfigResponse {
     pub app_domain: Option<String>,
@@ -211,9 +494,116 @@ pub struct GetConfigResponse {
     pub pwa_icon_maskable_sizes: Option<String>,
     pub use_push_notifications: Option<bool>,
     pub notifications_icon_name: Option<String>,
+    pub vapid_public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetConfigHistoryResponse {
+    pub versions: Vec<ConfigVersion>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct DeleteSubscriptionRequest {
     pub endpoint: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct PostOrganizerTokenRequest {
+    pub name: String,
+    pub roles: Vec<Role>,
+    // An RFC 3339 timestamp. Tokens without an expiry are valid until they're revoked.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostOrganizerTokenResponse {
+    pub id: String,
+    // This is the only time the token is returned; we only store a hash of it.
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizerTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub roles: Vec<Role>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub revoked_at: Option<String>,
+}
+
+impl From<OrganizerToken> for OrganizerTokenInfo {
+    fn from(token: OrganizerToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            roles: token.roles,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            revoked_at: token.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetOrganizerTokensResponse {
+    pub tokens: Vec<OrganizerTokenInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetAuditResponse {
+    pub entries: Vec<AuditEntry>,
+    // Pass this back as `cursor` to get the next page. This is absent on the last page.
+    pub cursor: Option<String>,
+}
+
+// The rows we skipped the last time we refreshed each cached dataset, because they don't match what
+// we expect.
+#[derive(Debug, Serialize)]
+pub struct GetValidationResponse {
+    pub reports: Vec<noco::ValidationReport>,
+}
+
+// How refreshing one cached dataset from NocoDB last went. We keep the last success and the last
+// failure separately, so a failure doesn't hide how old the cached data is.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStatus {
+    pub dataset: String,
+    pub last_refreshed_at: Option<String>,
+    pub refresh_duration_ms: Option<u64>,
+    // The size of the value in KV, compressed.
+    pub size_bytes: Option<u64>,
+    pub last_failure: Option<RefreshFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshFailure {
+    pub failed_at: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetCacheResponse {
+    pub datasets: Vec<CacheStatus>,
+}
+
+// Events that attendees won't see and likely mistakes in the schedule, checked against NocoDB as
+// of `checked_at`.
+#[derive(Debug, Serialize)]
+pub struct GetLintResponse {
+    pub checked_at: String,
+    pub issues: Vec<noco::LintIssue>,
+}
+
+// This is the JSON from `PushSubscription.toJSON()` in the browser, plus what we know about the
+// client that registered it.
+#[derive(Debug, Deserialize)]
+pub struct PostSubscriptionRequest {
+    #[serde(flatten)]
+    pub subscription: push::Subscription,
+    // The `applicationServerKey` the subscription was created with, base64url-encoded. Older
+    // clients don't send it.
+    #[serde(default)]
+    pub vapid_public_key: Option<String>,
+}
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use worker::{console_error, kv::KvStore};
+
+use crate::{
+    auth::{Actor, path_param},
+    env::EnvName,
+    kv,
+};
+
+// A record of a request to the admin API that changed something, so we can tell who did what and
+// when. We don't record request bodies, because they can contain secrets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: String,
+    pub actor: Option<Actor>,
+    pub method: String,
+    // The route that was matched, like `/admin/env/{env_name}/bases`.
+    pub route: String,
+    // The path that was requested, including the query string.
+    pub path: String,
+    pub status: u16,
+    // What the request did, like which config keys it changed, if the handler said.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+// Handlers add this to their response to describe what they did in the audit log. It must not
+// contain secrets.
+#[derive(Debug, Clone)]
+pub struct AuditSummary(pub String);
+
+// Entries are stored in KV key metadata, which is limited to 1024 bytes, so we cap the fields that
+// can be long.
+const MAX_FIELD_LEN: usize = 256;
+
+fn truncate(mut value: String) -> String {
+    if value.len() > MAX_FIELD_LEN {
+        let mut end = MAX_FIELD_LEN;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        value.truncate(end);
+        value.push('…');
+    }
+
+    value
+}
+
+// This must run inside the admin auth layer so it can see who made the request. Entries for routes
+// that aren't scoped to an environment, like aliases, go in a global log.
+pub async fn audit_middleware(State(kv): State<KvStore>, request: Request, next: Next) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+
+    let env_name = path_param(&mut parts, "env_name").await.map(EnvName::from);
+    let actor = parts.extensions.get::<Actor>().cloned();
+    let method = parts.method.to_string();
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|matched_path| matched_path.as_str().to_string())
+        .unwrap_or_default();
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let summary = response
+        .extensions()
+        .get::<AuditSummary>()
+        .map(|summary| truncate(summary.0.clone()));
+
+    let entry = AuditEntry {
+        time: chrono::Utc::now().to_rfc3339(),
+        actor,
+        method,
+        route,
+        path: truncate(path),
+        status: response.status().as_u16(),
+        summary,
+    };
+
+    // The change has already happened by this point, so failing the request wouldn't help anyone.
+    if let Err(err) = kv::put_audit_entry(&kv, env_name.as_ref(), &entry).await {
+        console_error!("Failed to record audit entry: {err}");
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_short_values() {
+        assert_eq!(
+            truncate("/admin/env/test/config".to_string()),
+            "/admin/env/test/config"
+        );
+    }
+
+    #[test]
+    fn truncate_cuts_on_char_boundaries() {
+        let truncated = truncate("é".repeat(MAX_FIELD_LEN));
+
+        assert!(truncated.len() <= MAX_FIELD_LEN + '…'.len_utf8());
+        assert!(truncated.ends_with('…'));
+    }
+}
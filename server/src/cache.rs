@@ -3,24 +3,107 @@ use std::time::Duration;
 use axum::{
     body::Body,
     extract::Request,
-    http::{self, HeaderValue, StatusCode, Uri, header},
+    http::{self, HeaderMap, HeaderName, HeaderValue, StatusCode, Uri, header},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use worker::{Cache, console_error};
+use worker::{Cache, EncodeBody, Url, console_error};
 
 use crate::{api::DataResponseEnvelope, env::EnvName, error::Error};
 
-// Convert a URL to a cache key. We drop query params; our API endpoints don't use them.
-pub fn cache_key_uri(uri: &Uri) -> anyhow::Result<Uri> {
-    let mut parts = uri.clone().into_parts();
-    let path = parts
-        .path_and_query
-        .as_ref()
-        .map_or("/", |path_and_query| path_and_query.path());
-    parts.path_and_query = Some(path.parse()?);
-    Ok(Uri::from_parts(parts)?)
+// Convert a URL to a cache key. We keep only the query params an endpoint uses, in a canonical
+// order, so that equivalent requests share a cache entry and made-up params can't be used to get
+// around the cache.
+pub fn cache_key_uri(uri: &Uri, params: &[&str]) -> anyhow::Result<Uri> {
+    let url = Url::parse(&uri.to_string())?;
+
+    let pairs = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(key, value)| params.contains(&key.as_str()) && !value.is_empty())
+        .collect::<Vec<_>>();
+
+    cache_key_uri_with(uri, pairs)
+}
+
+// The same, but with query params we've already parsed and normalized.
+pub fn cache_key_uri_with<K, V>(uri: &Uri, mut pairs: Vec<(K, V)>) -> anyhow::Result<Uri>
+where
+    K: AsRef<str> + Ord,
+    V: AsRef<str> + Ord,
+{
+    let mut url = Url::parse(&uri.to_string())?;
+
+    pairs.sort();
+
+    url.set_query(None);
+    url.set_fragment(None);
+
+    if !pairs.is_empty() {
+        url.query_pairs_mut().extend_pairs(pairs);
+    }
+
+    Ok(url.as_str().parse()?)
+}
+
+// A hash of a JSON value that doesn't depend on key order or formatting.
+pub fn content_hash<T: Serialize>(value: &T) -> String {
+    let mut hash_buf = Vec::new();
+    serde_json_canonicalizer::to_writer(value, &mut hash_buf).ok();
+    blake3::hash(&hash_buf).to_hex().to_string()
+}
+
+// The content hash from an ETag we sent, so clients can pass ETags back to us exactly as they got
+// them.
+pub fn etag_hash(etag: &str) -> &str {
+    let etag = etag.trim();
+    etag.strip_prefix("W/").unwrap_or(etag).trim_matches('"')
+}
+
+// Which cache a response's data came from, so we can tell why a client has old data without
+// reading the worker logs. We send it with how old the data is, in seconds, when we know.
+pub const CACHE_HEADER: HeaderName = HeaderName::from_static("x-fanjam-cache");
+pub const CACHE_AGE_HEADER: HeaderName = HeaderName::from_static("x-fanjam-cache-age");
+
+// When we put a response in the edge cache, so we know how old it is when we get it back.
+const CACHED_AT_HEADER: &str = "X-FanJam-Cached-At";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheSource {
+    // The edge cache in this datacenter.
+    EdgeHit,
+    // The persistent cache in KV, recently enough that we serve it as fresh.
+    PersistentFresh,
+    // The persistent cache in KV, which we're refreshing in the background.
+    PersistentStale,
+    // NocoDB, because the persistent cache was empty.
+    Upstream,
+}
+
+impl CacheSource {
+    fn header_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            Self::EdgeHit => "edge-hit",
+            Self::PersistentFresh => "persistent-fresh",
+            Self::PersistentStale => "persistent-stale",
+            Self::Upstream => "upstream",
+        })
+    }
+}
+
+pub fn set_cache_headers(
+    headers: &mut HeaderMap,
+    source: CacheSource,
+    cached_at: Option<DateTime<Utc>>,
+) {
+    headers.insert(CACHE_HEADER, source.header_value());
+
+    if let Some(cached_at) = cached_at {
+        let age = (Utc::now() - cached_at).num_seconds().max(0);
+        headers.insert(CACHE_AGE_HEADER, HeaderValue::from(age));
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -45,11 +128,8 @@ where
         let etag = if stale {
             None
         } else {
-            let mut hash_buf = Vec::new();
-            serde_json_canonicalizer::to_writer(&self.0.value, &mut hash_buf).ok();
-            let hash = blake3::hash(&hash_buf);
             Some(
-                HeaderValue::from_str(&format!("W/\"{}\"", hash.to_hex()))
+                HeaderValue::from_str(&format!("W/\"{}\"", content_hash(&self.0.value)))
                     .expect("Invalid ETag header value"),
             )
         };
@@ -89,6 +169,51 @@ where
     }
 }
 
+// A response from the persistent cache whose body we serialized and compressed when we cached it.
+// These are always stale, so there's no ETag.
+pub fn precompressed_response(body: Vec<u8>) -> Response {
+    let mut response = (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static("public, no-cache"),
+            ),
+            (header::CONTENT_ENCODING, HeaderValue::from_static("br")),
+            (header::VARY, HeaderValue::from_static("Accept-Encoding")),
+        ],
+        body,
+    )
+        .into_response();
+
+    // Otherwise the runtime would compress the body again.
+    response.extensions_mut().insert(EncodeBody::Manual);
+
+    response
+}
+
+// Whether an `Accept-Encoding` header allows brotli. See RFC 9110 §12.5.3.
+pub fn accepts_brotli(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let weight = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|weight| weight.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            name.eq_ignore_ascii_case("br") && weight > 0.0
+        })
+}
+
 pub async fn if_none_match_middleware(request: Request, next: Next) -> impl IntoResponse {
     let request_etag = request
         .headers()
@@ -112,6 +237,15 @@ pub async fn if_none_match_middleware(request: Request, next: Next) -> impl Into
     response
 }
 
+// Whether an `If-Match` header value matches the current strong ETag of a resource. See RFC 9110
+// §13.1.1. Unlike `If-None-Match`, this uses the strong comparison function.
+pub fn if_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
 fn weak_etags_match(a: &str, b: &str) -> bool {
     let a = a.strip_prefix("W/").unwrap_or(a);
     let b = b.strip_prefix("W/").unwrap_or(b);
@@ -125,10 +259,19 @@ pub async fn get_cdn_cache(cache: &Cache, uri: Uri) -> Result<Option<http::Respo
         .map_err(|err| Error::Internal(err.into()))?
         .map(http::Response::from)
         .map(|mut response| {
-            response.headers_mut().insert(
+            let headers = response.headers_mut();
+
+            headers.insert(
                 header::CACHE_CONTROL,
                 HeaderValue::from_static("public, no-cache"),
             );
+
+            let cached_at = headers
+                .remove(CACHED_AT_HEADER)
+                .and_then(|value| DateTime::parse_from_rfc3339(value.to_str().ok()?).ok())
+                .map(|cached_at| cached_at.to_utc());
+            set_cache_headers(headers, CacheSource::EdgeHit, cached_at);
+
             response
         }))
 }
@@ -152,6 +295,10 @@ pub async fn put_cdn_cache(
             .headers_mut()
             .set("Cache-Tag", &format!("env/{}", env_name))?;
 
+        response
+            .headers_mut()
+            .set(CACHED_AT_HEADER, &Utc::now().to_rfc3339())?;
+
         cache.put(uri.to_string(), response).await?;
 
         Ok(())
@@ -161,3 +308,107 @@ pub async fn put_cdn_cache(
         console_error!("Failed to put response in cache: {}", err);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_keys_keep_known_params_in_order() {
+        let uri: Uri = "https://api.fanjam.live/apps/abc/events/changes?junk=1&since=abc123"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            cache_key_uri(&uri, &["since"]).unwrap().to_string(),
+            "https://api.fanjam.live/apps/abc/events/changes?since=abc123"
+        );
+        assert_eq!(
+            cache_key_uri(&uri, &[]).unwrap().to_string(),
+            "https://api.fanjam.live/apps/abc/events/changes"
+        );
+    }
+
+    // Handlers must treat an empty param as absent for this to be safe.
+    #[test]
+    fn cache_keys_drop_empty_params() {
+        let uri: Uri = "https://api.fanjam.live/apps/abc/events/changes?since="
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            cache_key_uri(&uri, &["since"]).unwrap().to_string(),
+            "https://api.fanjam.live/apps/abc/events/changes"
+        );
+    }
+
+    #[test]
+    fn cache_keys_from_parsed_params_are_in_order() {
+        let uri: Uri = "https://api.fanjam.live/apps/abc/events?location=&junk=1"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            cache_key_uri_with(&uri, vec![("tag", "18+"), ("day", "2026-07-04")])
+                .unwrap()
+                .to_string(),
+            "https://api.fanjam.live/apps/abc/events?day=2026-07-04&tag=18%2B"
+        );
+        assert_eq!(
+            cache_key_uri_with::<&str, &str>(&uri, Vec::new())
+                .unwrap()
+                .to_string(),
+            "https://api.fanjam.live/apps/abc/events"
+        );
+    }
+
+    #[test]
+    fn accepts_etags_and_bare_hashes() {
+        assert_eq!(etag_hash("W/\"abc123\""), "abc123");
+        assert_eq!(etag_hash("abc123"), "abc123");
+    }
+
+    #[test]
+    fn parses_accept_encoding() {
+        let accepts = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::ACCEPT_ENCODING,
+                HeaderValue::from_str(value).unwrap(),
+            );
+            accepts_brotli(&headers)
+        };
+
+        assert!(accepts("gzip, deflate, br, zstd"));
+        assert!(accepts("gzip;q=1.0, BR;q=0.5"));
+        assert!(!accepts("gzip, br;q=0"));
+        assert!(!accepts("gzip, brotli"));
+        assert!(!accepts_brotli(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn reports_cache_source_and_age() {
+        let mut headers = HeaderMap::new();
+        set_cache_headers(
+            &mut headers,
+            CacheSource::PersistentStale,
+            Some(Utc::now() - chrono::TimeDelta::seconds(90)),
+        );
+
+        assert_eq!(headers[CACHE_HEADER], "persistent-stale");
+        assert!(
+            headers[CACHE_AGE_HEADER]
+                .to_str()
+                .unwrap()
+                .parse::<i64>()
+                .unwrap()
+                >= 90
+        );
+
+        let mut headers = HeaderMap::new();
+        set_cache_headers(&mut headers, CacheSource::EdgeHit, None);
+
+        assert_eq!(headers[CACHE_HEADER], "edge-hit");
+        assert!(!headers.contains_key(CACHE_AGE_HEADER));
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    api::{DataResponseEnvelope, SectionBody},
+    env::EnvName,
+};
+
+// Values in the persistent cache start with these bytes and then a format version. Values we
+// cached before we started compressing them are plain JSON, which can't start with these bytes, so
+// we can still read them.
+const MAGIC: &[u8] = b"FJ";
+
+// The value, compressed, with its length before it so we can find where it ends, and then the
+// endpoint's response body, compressed.
+const FORMAT_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 1;
+
+// Brotli, because browsers accept it, so we can send the compressed body as-is. Higher qualities
+// compress better but take much longer, and we compress on every refresh.
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW: u32 = 22;
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+fn compress(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+
+    {
+        let mut writer = brotli::CompressorWriter::new(
+            &mut compressed,
+            BROTLI_BUFFER_SIZE,
+            BROTLI_QUALITY,
+            BROTLI_WINDOW,
+        );
+        writer.write_all(bytes)?;
+        writer.flush()?;
+    }
+
+    Ok(compressed)
+}
+
+fn decompress(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    brotli::Decompressor::new(bytes, BROTLI_BUFFER_SIZE).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+// Encode a value for the persistent cache. Along with the value, we store the response body its
+// endpoint returns when it serves the value from the persistent cache, so we can send that without
+// decompressing and rebuilding it.
+pub fn encode<T>(env_name: &EnvName, value: &T) -> anyhow::Result<Vec<u8>>
+where
+    T: SectionBody + Serialize + ?Sized,
+{
+    let compressed_value = compress(&serde_json::to_vec(value)?)?;
+
+    // Responses from the persistent cache are always marked stale.
+    let compressed_body = compress(&serde_json::to_vec(&DataResponseEnvelope {
+        stale: true,
+        value: value.section_body(env_name),
+    })?)?;
+
+    let value_len = u32::try_from(compressed_value.len())?;
+
+    let mut bytes =
+        Vec::with_capacity(HEADER_LEN + 4 + compressed_value.len() + compressed_body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(FORMAT_VERSION);
+    bytes.extend_from_slice(&value_len.to_be_bytes());
+    bytes.extend_from_slice(&compressed_value);
+    bytes.extend_from_slice(&compressed_body);
+
+    Ok(bytes)
+}
+
+// A value as we read it from the persistent cache. We don't decode it until it's needed, since we
+// may be able to send the precompressed body instead.
+#[derive(Debug, Clone)]
+pub struct CacheEntry<T> {
+    bytes: Vec<u8>,
+    cached_at: Option<DateTime<Utc>>,
+    value: PhantomData<fn() -> T>,
+}
+
+// The parts of a value in the current format.
+struct Parts<'a> {
+    value: &'a [u8],
+    body: &'a [u8],
+}
+
+impl<T> CacheEntry<T> {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            cached_at: None,
+            value: PhantomData,
+        }
+    }
+
+    pub fn with_cached_at(self, cached_at: Option<DateTime<Utc>>) -> Self {
+        Self { cached_at, ..self }
+    }
+
+    // When we cached this, if we know.
+    pub fn cached_at(&self) -> Option<DateTime<Utc>> {
+        self.cached_at
+    }
+
+    // `None` if this is plain JSON from before we compressed values.
+    fn parts(&self) -> anyhow::Result<Option<Parts<'_>>> {
+        let Some(rest) = self.bytes.strip_prefix(MAGIC) else {
+            return Ok(None);
+        };
+
+        let (&version, rest) = rest
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Cached value is missing its format version."))?;
+
+        if version != FORMAT_VERSION {
+            anyhow::bail!("Cached value has unknown format version {version}.");
+        }
+
+        let (value_len, rest) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| anyhow::anyhow!("Cached value is truncated."))?;
+        let value_len = u32::from_be_bytes(*value_len) as usize;
+
+        if rest.len() < value_len {
+            anyhow::bail!("Cached value is truncated.");
+        }
+
+        let (value, body) = rest.split_at(value_len);
+
+        Ok(Some(Parts { value, body }))
+    }
+
+    pub fn value(&self) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        match self.parts()? {
+            Some(parts) => Ok(serde_json::from_slice(&decompress(parts.value)?)?),
+            None => Ok(serde_json::from_slice(&self.bytes)?),
+        }
+    }
+
+    // The endpoint's response body for this value, compressed with brotli, if we stored one.
+    pub fn precompressed_body(&self) -> Option<&[u8]> {
+        match self.parts() {
+            Ok(Some(parts)) if !parts.body.is_empty() => Some(parts.body),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noco;
+
+    fn events() -> Vec<noco::Event> {
+        vec![noco::Event {
+            id: "1".to_string(),
+            name: "Opening ceremony".to_string(),
+            summary: None,
+            description: Some("Welcome to the con!".repeat(100)),
+            start_time: "2026-07-04T10:00:00Z".to_string(),
+            end_time: None,
+            location: Some("Main Hall".to_string()),
+            category: None,
+            people: Vec::new(),
+            tags: Vec::new(),
+        }]
+    }
+
+    fn env_name() -> EnvName {
+        EnvName::from("test".to_string())
+    }
+
+    #[test]
+    fn round_trips_compressed_values() {
+        let events = events();
+        let bytes = encode(&env_name(), &events).unwrap();
+        let entry = CacheEntry::<Vec<noco::Event>>::from_bytes(bytes.clone());
+
+        assert!(bytes.len() < serde_json::to_vec(&events).unwrap().len());
+        assert_eq!(
+            serde_json::to_value(entry.value().unwrap()).unwrap(),
+            serde_json::to_value(&events).unwrap()
+        );
+    }
+
+    #[test]
+    fn stores_the_stale_response_body() {
+        let events = events();
+        let entry =
+            CacheEntry::<Vec<noco::Event>>::from_bytes(encode(&env_name(), &events).unwrap());
+
+        let body = decompress(entry.precompressed_body().unwrap()).unwrap();
+
+        assert_eq!(
+            body,
+            serde_json::to_vec(&DataResponseEnvelope {
+                stale: true,
+                value: events.section_body(&env_name()),
+            })
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn reads_plain_json_values() {
+        let events = events();
+        let entry =
+            CacheEntry::<Vec<noco::Event>>::from_bytes(serde_json::to_vec(&events).unwrap());
+
+        assert_eq!(entry.value().unwrap().len(), 1);
+        assert!(entry.precompressed_body().is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let entry = CacheEntry::<Vec<noco::Event>>::from_bytes(b"FJ\x09".to_vec());
+
+        assert!(entry.value().is_err());
+        assert!(entry.precompressed_body().is_none());
+    }
+}
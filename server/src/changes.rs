@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{Event, GetEventChangesResponse, GetEventsResponse},
+    cache::content_hash,
+    noco,
+};
+
+// How long we keep a snapshot after it was last the current version. A client that hasn't synced
+// for longer than this gets the full list of events again.
+pub const EVENTS_SNAPSHOT_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// The content hash of each event in one version of the schedule, by event ID. This is all we need
+// to tell a client which events changed since that version.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventsSnapshot {
+    pub events: HashMap<String, String>,
+}
+
+// The version of a list of events. This is the same hash as the ETag of the unfiltered events
+// endpoint, so clients can sync from the ETag of their last full fetch.
+pub fn events_version(events: &[noco::Event]) -> String {
+    content_hash(&GetEventsResponse::from(events.to_vec()))
+}
+
+pub fn events_snapshot(events: &[noco::Event]) -> EventsSnapshot {
+    EventsSnapshot {
+        events: events
+            .iter()
+            .cloned()
+            .map(Event::from)
+            .map(|event| (event.id.clone(), content_hash(&event)))
+            .collect(),
+    }
+}
+
+// The events that were added, changed, or removed since the version `since`. If we don't have a
+// snapshot of that version, we return every event and the client should replace what it has.
+pub fn event_changes(
+    events: Vec<noco::Event>,
+    since: Option<&str>,
+    snapshot: Option<&EventsSnapshot>,
+) -> GetEventChangesResponse {
+    let version = events_version(&events);
+
+    if since == Some(version.as_str()) {
+        return GetEventChangesResponse {
+            version,
+            full: false,
+            changed: Vec::new(),
+            removed: Vec::new(),
+        };
+    }
+
+    let events = events.into_iter().map(Event::from).collect::<Vec<_>>();
+
+    let Some(snapshot) = snapshot else {
+        return GetEventChangesResponse {
+            version,
+            full: true,
+            changed: events,
+            removed: Vec::new(),
+        };
+    };
+
+    let current_ids = events
+        .iter()
+        .map(|event| event.id.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut removed = snapshot
+        .events
+        .keys()
+        .filter(|id| !current_ids.contains(id.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    removed.sort();
+
+    let changed = events
+        .into_iter()
+        .filter(|event| snapshot.events.get(&event.id) != Some(&content_hash(event)))
+        .collect();
+
+    GetEventChangesResponse {
+        version,
+        full: false,
+        changed,
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, name: &str) -> noco::Event {
+        noco::Event {
+            id: id.to_string(),
+            name: name.to_string(),
+            summary: None,
+            description: None,
+            start_time: "2026-07-04T10:00:00Z".to_string(),
+            end_time: None,
+            location: None,
+            category: None,
+            people: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn ids(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.id.as_str()).collect()
+    }
+
+    #[test]
+    fn reports_added_changed_and_removed_events() {
+        let before = vec![
+            event("1", "Opening"),
+            event("2", "Panel"),
+            event("3", "Dance"),
+        ];
+        let after = vec![
+            event("1", "Opening"),
+            event("2", "Panel (moved)"),
+            event("4", "Karaoke"),
+        ];
+
+        let since = events_version(&before);
+        let changes = event_changes(after.clone(), Some(&since), Some(&events_snapshot(&before)));
+
+        assert!(!changes.full);
+        assert_eq!(changes.version, events_version(&after));
+        assert_eq!(ids(&changes.changed), ["2", "4"]);
+        assert_eq!(changes.removed, ["3"]);
+    }
+
+    #[test]
+    fn returns_nothing_for_the_current_version() {
+        let events = vec![event("1", "Opening")];
+        let version = events_version(&events);
+
+        let changes = event_changes(events, Some(&version), None);
+
+        assert!(!changes.full);
+        assert!(changes.changed.is_empty());
+        assert!(changes.removed.is_empty());
+    }
+
+    #[test]
+    fn returns_everything_for_an_unknown_version() {
+        let changes = event_changes(vec![event("1", "Opening")], Some("unknown"), None);
+
+        assert!(changes.full);
+        assert_eq!(ids(&changes.changed), ["1"]);
+    }
+}
@@ -1,10 +1,12 @@
 use std::sync::OnceLock;
 use std::time::Duration;
 
+use anyhow::Context;
 use worker::Env;
 
 use crate::auth;
 use crate::cf;
+use crate::crypto;
 use crate::neon;
 use crate::push;
 
@@ -14,7 +16,8 @@ struct Config {
     api_domain: String,
     cloudflare_api_token: cf::ApiToken,
     cloudflare_zone_id: cf::ZoneId,
-    admin_api_token: auth::ApiToken,
+    admin_api_tokens: auth::ApiTokenSet,
+    config_encryption_key: crypto::EncryptionKey,
     neon_api_token: neon::ApiToken,
     neon_org_id: String,
     neon_default_branch_name: String,
@@ -22,8 +25,8 @@ struct Config {
     r2_asset_cache_ttl_seconds: u32,
     // `None` if the VAPID secret for Web Push hasn't been set up. This
     // isn't a fatal error; it just means push notifications won't work.
-    vapid: Option<push::VapidKey>,
-    noco_webhook_token: Option<auth::ApiToken>,
+    vapid: Option<push::VapidKeys>,
+    noco_webhook_tokens: Option<auth::ApiTokenSet>,
 }
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -36,11 +39,16 @@ pub fn init(env: &Env) -> anyhow::Result<()> {
             api_domain: env.var("API_DOMAIN")?.to_string(),
             cloudflare_api_token: env.secret("CLOUDFLARE_API_TOKEN")?.to_string().into(),
             cloudflare_zone_id: env.secret("CLOUDFLARE_ZONE_ID")?.to_string().into(),
-            admin_api_token: env
+            admin_api_tokens: env
                 .secret("ADMIN_API_TOKEN")?
                 .to_string()
                 .as_str()
                 .try_into()?,
+            config_encryption_key: env
+                .secret("CONFIG_ENCRYPTION_KEY")?
+                .to_string()
+                .as_str()
+                .try_into()?,
             neon_api_token: env.secret("NEON_API_TOKEN")?.to_string().into(),
             neon_org_id: env.secret("NEON_ORG_ID")?.to_string(),
             neon_default_branch_name: env.secret("NEON_DEFAULT_BRANCH_NAME")?.to_string(),
@@ -53,27 +61,41 @@ pub fn init(env: &Env) -> anyhow::Result<()> {
                 .to_string()
                 .parse()?,
             vapid: init_vapid(env),
-            noco_webhook_token: env
+            // This is optional, but if it's set, it must parse.
+            noco_webhook_tokens: env
                 .secret("NOCO_WEBHOOK_TOKEN")
                 .ok()
-                .and_then(|s| auth::ApiToken::try_from(s.to_string().as_str()).ok()),
+                .map(|s| {
+                    auth::ApiTokenSet::try_from(s.to_string().as_str())
+                        .context("NOCO_WEBHOOK_TOKEN is malformed")
+                })
+                .transpose()?,
         })
         .ok();
 
     Ok(())
 }
 
-fn init_vapid(env: &Env) -> Option<push::VapidKey> {
-    let private_key = env.secret("VAPID_PRIVATE_KEY").ok()?.to_string();
+fn init_vapid_key(env: &Env, secret_name: &str, subject: &str) -> Option<push::VapidKey> {
+    let private_key = env.secret(secret_name).ok()?.to_string();
     if private_key.is_empty() {
         return None;
     }
-    let subject = env.var("VAPID_SUBJECT").ok()?.to_string();
     push::VapidKey::from_base64url(&private_key, subject)
-        .inspect_err(|e| worker::console_warn!("VAPID key not loaded: {e}"))
+        .inspect_err(|e| worker::console_warn!("VAPID key {secret_name} not loaded: {e}"))
         .ok()
 }
 
+// To rotate the VAPID key, move the current private key to
+// `VAPID_PREVIOUS_PRIVATE_KEY` and put the new one in `VAPID_PRIVATE_KEY`. Subscriptions made
+// against the previous key keep working until their browsers re-subscribe.
+fn init_vapid(env: &Env) -> Option<push::VapidKeys> {
+    let subject = env.var("VAPID_SUBJECT").ok()?.to_string();
+    let current = init_vapid_key(env, "VAPID_PRIVATE_KEY", &subject)?;
+    let previous = init_vapid_key(env, "VAPID_PREVIOUS_PRIVATE_KEY", &subject);
+    Some(push::VapidKeys::new(current, previous))
+}
+
 fn get_config() -> &'static Config {
     CONFIG.get().expect("config not initialized")
 }
@@ -90,12 +112,12 @@ pub fn api_domain() -> &'static str {
     get_config().api_domain.as_str()
 }
 
-pub fn vapid_key() -> Option<push::VapidKey> {
+pub fn vapid_keys() -> Option<push::VapidKeys> {
     get_config().vapid.clone()
 }
 
-pub fn noco_webhook_token() -> Option<auth::ApiToken> {
-    get_config().noco_webhook_token.clone()
+pub fn noco_webhook_tokens() -> Option<auth::ApiTokenSet> {
+    get_config().noco_webhook_tokens.clone()
 }
 
 pub fn cloudflare_api_token() -> cf::ApiToken {
@@ -106,8 +128,12 @@ pub fn cloudflare_zone_id() -> cf::ZoneId {
     get_config().cloudflare_zone_id.clone()
 }
 
-pub fn admin_api_token() -> auth::ApiToken {
-    get_config().admin_api_token.clone()
+pub fn admin_api_tokens() -> auth::ApiTokenSet {
+    get_config().admin_api_tokens.clone()
+}
+
+pub fn config_encryption_key() -> crypto::EncryptionKey {
+    get_config().config_encryption_key.clone()
 }
 
 pub fn neon_api_token() -> neon::ApiToken {
@@ -0,0 +1,517 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use worker::{Bucket, Url};
+
+use crate::{
+    api::FieldError,
+    env::{Config, ConfigChange, EnvName},
+};
+
+// Documentation and metadata for each config key in the environment-specific configuration. Keep
+// this up to date.
+pub const CONFIG_SPEC: &str = include_str!("./config-spec.json");
+
+// The expected format of a config value. Keys with a `format` in the config spec are validated
+// against it whenever the config is written, so that a typo can't break the app for attendees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    // An IANA timezone identifier, like `America/New_York`.
+    Timezone,
+    // Any CSS color value, like `#ff0000` or `rebeccapurple`.
+    CssColor,
+    // An `image/*` media type, like `image/png`.
+    ImageType,
+    // The `sizes` attribute of a web app manifest icon, like `192x192 512x512` or `any`.
+    IconSizes,
+    // An absolute `http`, `https`, or `mailto` URL.
+    Url,
+    // The name of an asset uploaded for this environment.
+    Asset,
+    // A calendar date, like `2026-07-04`.
+    Date,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecEntry {
+    pub key: String,
+    pub help: String,
+    pub sensitive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+}
+
+static SPEC: OnceLock<Vec<SpecEntry>> = OnceLock::new();
+
+pub fn spec() -> &'static [SpecEntry] {
+    SPEC.get_or_init(|| serde_json::from_str(CONFIG_SPEC).expect("config spec is not valid JSON"))
+}
+
+// Sensitive values are replaced with this in admin API responses unless the caller explicitly asks
+// for them.
+pub const REDACTED: &str = "[REDACTED]";
+
+pub fn is_sensitive(key: &str) -> bool {
+    spec()
+        .iter()
+        .any(|entry| entry.sensitive && entry.key == key)
+}
+
+// Apply `f` to the value of every key marked as sensitive in the config spec. Sensitive keys must
+// be strings.
+pub fn map_sensitive(
+    config: &Config,
+    f: impl Fn(&str) -> anyhow::Result<String>,
+) -> anyhow::Result<Config> {
+    let mut values = serde_json::to_value(config)?;
+
+    for entry in spec().iter().filter(|entry| entry.sensitive) {
+        if let Some(value) = values.get_mut(&entry.key)
+            && let Some(plaintext) = value.as_str()
+        {
+            *value = Value::String(f(plaintext)?);
+        }
+    }
+
+    Ok(serde_json::from_value(values)?)
+}
+
+pub fn redact(config: &Config) -> anyhow::Result<Config> {
+    map_sensitive(config, |_| Ok(REDACTED.to_string()))
+}
+
+// We never store the old or new values of sensitive keys in the config history; we only record
+// that they changed.
+pub fn redact_changes(changes: Vec<ConfigChange>) -> Vec<ConfigChange> {
+    let redact_value = |value: Value| match value {
+        Value::Null => Value::Null,
+        _ => Value::String(REDACTED.to_string()),
+    };
+
+    changes
+        .into_iter()
+        .map(|change| {
+            if is_sensitive(&change.key) {
+                ConfigChange {
+                    key: change.key,
+                    old: redact_value(change.old),
+                    new: redact_value(change.new),
+                }
+            } else {
+                change
+            }
+        })
+        .collect()
+}
+
+// When an operator reads a redacted config, edits it, and writes it back, the sensitive values
+// will still be redacted. Keep the current value for those keys rather than overwriting the real
+// value with the placeholder.
+pub fn restore_redacted(config: &Config, current: &Config) -> anyhow::Result<Config> {
+    let current = serde_json::to_value(current)?;
+    let mut values = serde_json::to_value(config)?;
+
+    for entry in spec().iter().filter(|entry| entry.sensitive) {
+        if let Some(value) = values.get_mut(&entry.key)
+            && value.as_str() == Some(REDACTED)
+        {
+            *value = current.get(&entry.key).cloned().unwrap_or(Value::Null);
+        }
+    }
+
+    Ok(serde_json::from_value(values)?)
+}
+
+// Check every key in the config against the format declared for it in the config spec. This
+// returns one error per invalid key, rather than bailing on the first one, so the operator can fix
+// everything in one pass.
+pub async fn validate(
+    config: &Config,
+    env_name: &EnvName,
+    bucket: &Bucket,
+) -> anyhow::Result<Vec<FieldError>> {
+    let values = serde_json::to_value(config)?;
+    let mut errors = Vec::new();
+
+    for entry in spec() {
+        let Some(format) = entry.format else {
+            continue;
+        };
+
+        let Some(value) = values.get(&entry.key).and_then(|value| value.as_str()) else {
+            continue;
+        };
+
+        let result = match format {
+            Format::Asset => check_asset(env_name, bucket, value).await?,
+            _ => check_format(format, value),
+        };
+
+        if let Err(error) = result {
+            errors.push(FieldError {
+                key: entry.key.clone(),
+                error,
+            });
+        }
+    }
+
+    Ok(errors)
+}
+
+async fn check_asset(
+    env_name: &EnvName,
+    bucket: &Bucket,
+    name: &str,
+) -> anyhow::Result<Result<(), String>> {
+    // This must match the key that `get_asset` serves assets from.
+    let bucket_key = format!("env/{env_name}/{name}");
+
+    let object = bucket
+        .head(&bucket_key)
+        .await
+        .map_err(|err| anyhow::Error::msg(err.to_string()))?;
+
+    Ok(match object {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "No asset named `{name}` has been uploaded for this environment."
+        )),
+    })
+}
+
+fn check_format(format: Format, value: &str) -> Result<(), String> {
+    let is_valid = match format {
+        Format::Timezone => chrono_tz::Tz::from_str(value).is_ok(),
+        Format::CssColor => is_css_color(value),
+        Format::ImageType => is_image_type(value),
+        Format::IconSizes => is_icon_sizes(value),
+        Format::Url => is_url(value),
+        Format::Asset => true,
+        Format::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+    };
+
+    if is_valid {
+        return Ok(());
+    }
+
+    Err(match format {
+        Format::Timezone => "Must be an IANA timezone identifier, like `America/New_York`.",
+        Format::CssColor => "Must be a CSS color, like `#ff0000` or `rebeccapurple`.",
+        Format::ImageType => "Must be an image media type, like `image/png`.",
+        Format::IconSizes => {
+            "Must be a space-separated list of sizes, like `192x192 512x512`, or `any`."
+        }
+        Format::Url => "Must be an absolute `https`, `http`, or `mailto` URL.",
+        Format::Date => "Must be a date, like `2026-07-04`.",
+        Format::Asset => unreachable!(),
+    }
+    .to_string())
+}
+
+const CSS_COLOR_FUNCTIONS: &[&str] = &[
+    "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch", "color",
+];
+
+const CSS_NAMED_COLORS: &[&str] = &[
+    "transparent",
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+// We don't implement a full CSS parser here. This accepts hex colors, named colors, and anything
+// that looks like a color function with plausible arguments, which is enough to catch typos.
+fn is_css_color(value: &str) -> bool {
+    let value = value.trim().to_ascii_lowercase();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+
+    if let Some((name, args)) = value.split_once('(') {
+        let Some(args) = args.strip_suffix(')') else {
+            return false;
+        };
+
+        return CSS_COLOR_FUNCTIONS.contains(&name)
+            && !args.trim().is_empty()
+            && args
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || " ,.%/-+".contains(c));
+    }
+
+    CSS_NAMED_COLORS.contains(&value.as_str())
+}
+
+// See the `restricted-name` production in RFC 6838 §4.2.
+fn is_image_type(value: &str) -> bool {
+    let Some(subtype) = value.strip_prefix("image/") else {
+        return false;
+    };
+
+    let mut chars = subtype.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && subtype.len() <= 127
+        && chars.all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+}
+
+// See the `sizes` member of the web app manifest `ImageResource`.
+fn is_icon_sizes(value: &str) -> bool {
+    if value.trim() == "any" {
+        return true;
+    }
+
+    let is_dimension =
+        |s: &str| !s.is_empty() && !s.starts_with('0') && s.chars().all(|c| c.is_ascii_digit());
+
+    let mut sizes = value.split_ascii_whitespace().peekable();
+
+    sizes.peek().is_some()
+        && sizes.all(|size| {
+            size.to_ascii_lowercase()
+                .split_once('x')
+                .is_some_and(|(width, height)| is_dimension(width) && is_dimension(height))
+        })
+}
+
+fn is_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| match url.scheme() {
+        "https" | "http" => url.host_str().is_some(),
+        "mailto" => !url.path().is_empty(),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_config_key_is_in_the_spec() {
+        // If this fails, you added a key to `env::Config` without documenting it in the spec.
+        let config = serde_json::to_value(Config::default()).unwrap();
+        let spec_keys = spec()
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect::<Vec<_>>();
+
+        for key in config.as_object().unwrap().keys() {
+            assert!(
+                spec_keys.contains(&key.as_str()),
+                "`{key}` is missing from the spec"
+            );
+        }
+    }
+
+    #[test]
+    fn sensitive_keys_are_strings() {
+        // `map_sensitive` replaces values with strings, so a sensitive key of any other type would
+        // fail to deserialize.
+        for entry in spec().iter().filter(|entry| entry.sensitive) {
+            let config = serde_json::json!({ entry.key.clone(): "secret" });
+            assert!(
+                serde_json::from_value::<Config>(config).is_ok(),
+                "`{}` is sensitive but not a string",
+                entry.key,
+            );
+        }
+    }
+
+    #[test]
+    fn redacted_values_are_restored() {
+        let current = Config {
+            config_db_password: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+
+        let redacted = redact(&current).unwrap();
+        assert_eq!(redacted.config_db_password.as_deref(), Some(REDACTED));
+
+        let restored = restore_redacted(&redacted, &current).unwrap();
+        assert_eq!(restored.config_db_password.as_deref(), Some("hunter2"));
+    }
+
+    #[test]
+    fn accepts_valid_values() {
+        assert!(check_format(Format::Timezone, "America/New_York").is_ok());
+        assert!(check_format(Format::Timezone, "UTC").is_ok());
+        assert!(check_format(Format::CssColor, "#1a2B3c").is_ok());
+        assert!(check_format(Format::CssColor, "#fff8").is_ok());
+        assert!(check_format(Format::CssColor, "RebeccaPurple").is_ok());
+        assert!(check_format(Format::CssColor, "rgb(255 0 0 / 50%)").is_ok());
+        assert!(check_format(Format::ImageType, "image/svg+xml").is_ok());
+        assert!(check_format(Format::IconSizes, "192x192 512X512").is_ok());
+        assert!(check_format(Format::IconSizes, "any").is_ok());
+        assert!(check_format(Format::Url, "https://example.com/feedback").is_ok());
+        assert!(check_format(Format::Url, "mailto:feedback@example.com").is_ok());
+        assert!(check_format(Format::Date, "2026-07-04").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(check_format(Format::Timezone, "America/New York").is_err());
+        assert!(check_format(Format::Timezone, "EST5").is_err());
+        assert!(check_format(Format::CssColor, "#12345").is_err());
+        assert!(check_format(Format::CssColor, "reddish").is_err());
+        assert!(check_format(Format::CssColor, "rgb(255, 0, 0").is_err());
+        assert!(check_format(Format::CssColor, "url(javascript:alert(1))").is_err());
+        assert!(check_format(Format::ImageType, "text/html").is_err());
+        assert!(check_format(Format::ImageType, "png").is_err());
+        assert!(check_format(Format::IconSizes, "192").is_err());
+        assert!(check_format(Format::IconSizes, "0x192").is_err());
+        assert!(check_format(Format::IconSizes, "").is_err());
+        assert!(check_format(Format::Url, "example.com/feedback").is_err());
+        assert!(check_format(Format::Url, "javascript:alert(1)").is_err());
+        assert!(check_format(Format::Date, "2026-02-30").is_err());
+        assert!(check_format(Format::Date, "July 4").is_err());
+    }
+}
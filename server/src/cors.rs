@@ -1,9 +1,11 @@
 use axum::http::{
     Method,
-    header::{CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, RETRY_AFTER},
 };
 use tower_http::cors::{Any, CorsLayer};
 
+use crate::cache::{CACHE_AGE_HEADER, CACHE_HEADER};
+
 pub fn cors_layer() -> CorsLayer {
     CorsLayer::new()
         .allow_methods([
@@ -13,7 +15,7 @@ pub fn cors_layer() -> CorsLayer {
             Method::PATCH,
             Method::DELETE,
         ])
-        .allow_headers([CONTENT_TYPE, IF_NONE_MATCH])
-        .expose_headers([ETAG])
+        .allow_headers([CONTENT_TYPE, IF_MATCH, IF_NONE_MATCH])
+        .expose_headers([ETAG, RETRY_AFTER, CACHE_HEADER, CACHE_AGE_HEADER])
         .allow_origin(Any)
 }
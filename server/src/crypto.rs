@@ -0,0 +1,115 @@
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce, aead::Aead};
+use anyhow::Context;
+use base64::prelude::*;
+use rand::Rng;
+use secrecy::{ExposeSecret, SecretSlice};
+
+// Encrypted values are stored with this prefix so we can tell them apart from values that were
+// written in plaintext before we started encrypting them. The version lets us change the scheme
+// later without breaking old values.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+// A worker-level key for encrypting sensitive values at rest in KV, such as database passwords and
+// NocoDB API tokens. Rotating this key makes every value encrypted with it unreadable, so don't.
+#[derive(Debug, Clone)]
+pub struct EncryptionKey(SecretSlice<u8>);
+
+impl TryFrom<&str> for EncryptionKey {
+    type Error = anyhow::Error;
+
+    fn try_from(key: &str) -> Result<Self, Self::Error> {
+        let bytes = BASE64_STANDARD
+            .decode(key)
+            .context("failed to decode config encryption key")?;
+
+        if bytes.len() != KEY_LEN {
+            anyhow::bail!(
+                "config encryption key is {} bytes, expected {KEY_LEN}",
+                bytes.len()
+            );
+        }
+
+        Ok(Self(SecretSlice::from(bytes)))
+    }
+}
+
+impl EncryptionKey {
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.0.expose_secret()))
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> anyhow::Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce);
+
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("failed to encrypt value"))?;
+
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}",
+            BASE64_URL_SAFE_NO_PAD.encode([nonce.as_slice(), &ciphertext].concat())
+        ))
+    }
+
+    // Values without the encrypted prefix are returned as-is, because they were written before we
+    // started encrypting them. They get encrypted the next time they're written.
+    pub fn decrypt(&self, value: &str) -> anyhow::Result<String> {
+        let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(encoded)
+            .context("encrypted value is not valid base64url")?;
+
+        if bytes.len() < NONCE_LEN {
+            anyhow::bail!("encrypted value is too short");
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+        let plaintext = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("failed to decrypt value; was the key rotated?"))?;
+
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(byte: u8) -> EncryptionKey {
+        EncryptionKey::try_from(BASE64_STANDARD.encode([byte; KEY_LEN]).as_str()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_and_uses_a_fresh_nonce() {
+        let key = test_key(1);
+        let a = key.encrypt("hunter2").unwrap();
+        let b = key.encrypt("hunter2").unwrap();
+
+        assert!(a.starts_with(ENCRYPTED_PREFIX));
+        assert_ne!(a, b);
+        assert_eq!(key.decrypt(&a).unwrap(), "hunter2");
+        assert_eq!(key.decrypt(&b).unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn passes_through_legacy_plaintext() {
+        assert_eq!(test_key(1).decrypt("hunter2").unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn rejects_the_wrong_key() {
+        let ciphertext = test_key(1).encrypt("hunter2").unwrap();
+        assert!(test_key(2).decrypt(&ciphertext).is_err());
+    }
+}
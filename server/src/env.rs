@@ -1,6 +1,7 @@
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use worker::Url;
 
 // A random ID that forms part of the app URL gives to attendees.
@@ -123,8 +124,217 @@ pub struct Config {
     pub pwa_icon_maskable_sizes: Option<String>,
     pub use_push_notifications: Option<bool>,
     pub notifications_icon_name: Option<String>,
+    pub con_start_date: Option<String>,
+    pub con_end_date: Option<String>,
+    pub use_public_lint: Option<bool>,
 }
 
-// Documentation and metadata for each config key in the environment-specific configuration. Keep
-// this up to date.
-pub const CONFIG_SPEC: &str = include_str!("./config-spec.json");
+impl Config {
+    // A strong ETag for this config. Admin writes must send this back in `If-Match` so that two
+    // operators editing the config at once can't silently clobber each other's changes.
+    //
+    // Only call this on the config as we store it, with sensitive values encrypted. A hash of the
+    // plaintext would let anyone who can see the ETag check guesses at them offline.
+    pub fn etag(&self) -> anyhow::Result<String> {
+        let canonical = serde_json_canonicalizer::to_vec(self)?;
+        Ok(format!("\"{}\"", blake3::hash(&canonical).to_hex()))
+    }
+
+    // Apply a JSON Merge Patch (RFC 7396) to this config. Because the config is a flat map,
+    // setting a key to `null` in the patch unsets it.
+    //
+    // Unknown keys are rejected rather than dropped, so a typo in a patch fails instead of
+    // silently doing nothing.
+    pub fn merge_patch(&self, patch: &Value) -> anyhow::Result<Self> {
+        if let (Value::Object(patch), Value::Object(known)) =
+            (patch, serde_json::to_value(Self::default())?)
+            && let Some(key) = patch.keys().find(|key| !known.contains_key(*key))
+        {
+            anyhow::bail!("Unknown config key `{}`.", key);
+        }
+
+        let mut target = serde_json::to_value(self)?;
+        merge_patch(&mut target, patch);
+        Ok(serde_json::from_value(target)?)
+    }
+
+    // The keys which differ between this config and a newer one.
+    pub fn diff(&self, new: &Config) -> anyhow::Result<Vec<ConfigChange>> {
+        let old = serde_json::to_value(self)?;
+        let new = serde_json::to_value(new)?;
+
+        let (Value::Object(old), Value::Object(new)) = (old, new) else {
+            anyhow::bail!("Config did not serialize to a JSON object.");
+        };
+
+        let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| {
+                let old_value = old.get(key).cloned().unwrap_or(Value::Null);
+                let new_value = new.get(key).cloned().unwrap_or(Value::Null);
+
+                (old_value != new_value).then(|| ConfigChange {
+                    key: key.clone(),
+                    old: old_value,
+                    new: new_value,
+                })
+            })
+            .collect())
+    }
+}
+
+// A single key that changed between two versions of the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+// A snapshot of the config as it was written, kept so we can see what changed and roll back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigVersion {
+    pub version: u32,
+    pub created_at: String,
+    pub changes: Vec<ConfigChange>,
+    pub config: Config,
+}
+
+// See RFC 7396 §2.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+
+    let Value::Object(target) = target else {
+        unreachable!();
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_patch_matches_rfc_7396_examples() {
+        // A selection of the test cases from RFC 7396 Appendix A.
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (json!(["a", "b"]), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ];
+
+        for (mut target, patch, expected) in cases {
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, expected);
+        }
+    }
+
+    #[test]
+    fn merge_patch_unsets_config_keys() {
+        let config = Config {
+            timezone: Some("America/New_York".to_string()),
+            use_feedback: Some(true),
+            ..Default::default()
+        };
+
+        let patched = config
+            .merge_patch(&json!({"timezone": null, "feedback_url": "https://example.com"}))
+            .unwrap();
+
+        assert_eq!(patched.timezone, None);
+        assert_eq!(patched.use_feedback, Some(true));
+        assert_eq!(patched.feedback_url.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn merge_patch_rejects_unknown_keys() {
+        let config = Config::default();
+
+        assert!(config.merge_patch(&json!({"timezon": "UTC"})).is_err());
+        assert!(config.merge_patch(&json!({"timezon": null})).is_err());
+        assert!(config.merge_patch(&json!({"timezone": "UTC"})).is_ok());
+    }
+
+    #[test]
+    fn diff_lists_only_changed_keys() {
+        let old = Config {
+            timezone: Some("America/New_York".to_string()),
+            use_feedback: Some(true),
+            ..Default::default()
+        };
+        let new = Config {
+            timezone: Some("America/Chicago".to_string()),
+            use_feedback: Some(true),
+            cache_ttl: Some(1000),
+            ..Default::default()
+        };
+
+        let changes = old.diff(&new).unwrap();
+        let keys = changes.iter().map(|c| c.key.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(keys, ["cache_ttl", "timezone"]);
+        assert_eq!(changes[0].old, Value::Null);
+        assert_eq!(changes[1].new, "America/Chicago");
+    }
+
+    #[test]
+    fn etag_changes_with_config() {
+        let config = Config::default();
+        let changed = Config {
+            cache_ttl: Some(1000),
+            ..Default::default()
+        };
+
+        assert_eq!(config.etag().unwrap(), Config::default().etag().unwrap());
+        assert_ne!(config.etag().unwrap(), changed.etag().unwrap());
+    }
+}
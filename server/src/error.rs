@@ -1,8 +1,14 @@
-use axum::{Json, http::StatusCode, response::ErrorResponse};
+use std::time::Duration;
+
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{ErrorResponse, IntoResponse},
+};
 use thiserror::Error;
 use worker::console_error;
 
-use crate::api::ErrorResponse as ApiErrorResponse;
+use crate::api::{ErrorResponse as ApiErrorResponse, FieldError};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -39,9 +45,50 @@ pub enum Error {
     #[error("That custom domain is already assigned to a different environment.")]
     DomainInUse,
 
+    #[error("That environment ID is already assigned to a different environment.")]
+    EnvIdInUse,
+
     #[error("No custom domain is configured for this environment.")]
     NoEnvDomain,
 
+    #[error("The environment config is invalid.")]
+    InvalidConfig(Vec<FieldError>),
+
+    #[error("That version of the config does not exist.")]
+    NoConfigVersion,
+
+    #[error("Invalid config patch: {0}")]
+    InvalidConfigPatch(anyhow::Error),
+
+    #[error("You must send the current ETag of the config in an If-Match header.")]
+    MissingIfMatch,
+
+    #[error(
+        "The config was changed by someone else since you fetched it. Fetch it again and retry."
+    )]
+    ConfigChanged,
+
+    #[error("Invalid token expiry: {0}")]
+    InvalidTokenExpiry(anyhow::Error),
+
+    #[error("That organizer token does not exist.")]
+    NoOrganizerToken,
+
+    #[error("Invalid push subscription: {0}")]
+    InvalidSubscription(anyhow::Error),
+
+    #[error("Invalid event query: {0}")]
+    InvalidEventQuery(anyhow::Error),
+
+    #[error("Only the global admin token can read sensitive config values.")]
+    SensitiveConfigForbidden,
+
+    #[error("The schedule lint report is not public for this environment.")]
+    LintNotPublic,
+
+    #[error("Too many requests. Try again in {} seconds.", .0.as_secs())]
+    RateLimited(Duration),
+
     #[error("Internal server error: {0}")]
     Internal(anyhow::Error),
 }
@@ -60,7 +107,20 @@ impl Error {
             Error::AssetNotFound => StatusCode::NOT_FOUND,
             Error::InvalidDomain(_) => StatusCode::BAD_REQUEST,
             Error::DomainInUse => StatusCode::CONFLICT,
+            Error::EnvIdInUse => StatusCode::CONFLICT,
             Error::NoEnvDomain => StatusCode::NOT_FOUND,
+            Error::InvalidConfig(_) => StatusCode::BAD_REQUEST,
+            Error::NoConfigVersion => StatusCode::NOT_FOUND,
+            Error::InvalidConfigPatch(_) => StatusCode::BAD_REQUEST,
+            Error::MissingIfMatch => StatusCode::PRECONDITION_REQUIRED,
+            Error::ConfigChanged => StatusCode::PRECONDITION_FAILED,
+            Error::InvalidTokenExpiry(_) => StatusCode::BAD_REQUEST,
+            Error::NoOrganizerToken => StatusCode::NOT_FOUND,
+            Error::InvalidSubscription(_) => StatusCode::BAD_REQUEST,
+            Error::InvalidEventQuery(_) => StatusCode::BAD_REQUEST,
+            Error::SensitiveConfigForbidden => StatusCode::FORBIDDEN,
+            Error::LintNotPublic => StatusCode::NOT_FOUND,
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             Error::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -68,13 +128,36 @@ impl Error {
 
 impl From<Error> for ErrorResponse {
     fn from(error: Error) -> Self {
-        console_error!("Error: {}", error);
+        // Rate limiting is expected, and the middleware already logs it.
+        if !matches!(error, Error::RateLimited(_)) {
+            console_error!("Error: {}", error);
+        }
 
-        ErrorResponse::from((
+        let fields = match &error {
+            Error::InvalidConfig(fields) => fields.clone(),
+            _ => Vec::new(),
+        };
+
+        let retry_after = match &error {
+            Error::RateLimited(retry_after) => Some(retry_after.as_secs()),
+            _ => None,
+        };
+
+        let mut response = (
             error.status_code(),
             Json(ApiErrorResponse {
                 error: error.to_string(),
+                fields,
             }),
-        ))
+        )
+            .into_response();
+
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        ErrorResponse::from(response)
     }
 }
@@ -1,10 +1,20 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
 use worker::kv::{KvError, KvStore};
 
 use crate::{
-    api::Alias,
-    env::{Config, EnvDomain, EnvId, EnvName},
-    noco::{Announcement, ApiToken, BaseId, Event, File, Info, Page, TableInfo},
+    api::{Alias, CacheStatus},
+    audit::AuditEntry,
+    auth::OrganizerToken,
+    cache_entry::{self, CacheEntry},
+    changes::EventsSnapshot,
+    config, config_spec,
+    env::{Config, ConfigVersion, EnvDomain, EnvId, EnvName},
+    noco::{Announcement, ApiToken, BaseId, Event, File, Info, Page, TableInfo, ValidationReport},
     push,
 };
 
@@ -23,8 +33,11 @@ fn env_id_key(env_name: &EnvName) -> String {
 
 // We need to map environment ID to environment name because the client app will be making requests
 // to this service by the environment ID.
+const ID_ENV_KEY_PREFIX: &str = "id:";
+const ID_ENV_KEY_SUFFIX: &str = ":env";
+
 fn id_env_key(env_id: &EnvId) -> String {
-    format!("id:{env_id}:env")
+    format!("{ID_ENV_KEY_PREFIX}{env_id}{ID_ENV_KEY_SUFFIX}")
 }
 
 const ALIAS_KEY_PREFIX: &str = "alias:";
@@ -46,7 +59,8 @@ fn domain_env_key(domain: &EnvDomain) -> String {
     format!("domain:{domain}:env")
 }
 
-// The NocoDB API token for the environment. This is used to authenticate with the NocoDB API.
+// The NocoDB API token for the environment. This is used to authenticate with the NocoDB API. It's
+// encrypted at rest with the config encryption key.
 fn api_token_key(env_name: &EnvName) -> String {
     format!("env:{env_name}:api-token")
 }
@@ -65,11 +79,27 @@ fn base_id_key(env_name: &EnvName) -> String {
     format!("env:{env_name}:base-id")
 }
 
-// Environment-specific config values.
+// Environment-specific config values. Values marked as sensitive in the config spec are encrypted at
+// rest with the config encryption key.
 fn env_config_key(env_name: &EnvName) -> String {
     format!("env:{env_name}:config")
 }
 
+// Every version of the environment config that has been written, so we can tell what changed and
+// roll back. The version number is zero-padded so a `prefix` scan returns versions in order.
+fn config_history_key_prefix(env_name: &EnvName) -> String {
+    format!("env:{env_name}:config-history:")
+}
+
+fn config_history_key(env_name: &EnvName, version: u32) -> String {
+    format!("{}{:010}", config_history_key_prefix(env_name), version)
+}
+
+// The number of the most recent version of the environment config.
+fn config_version_key(env_name: &EnvName) -> String {
+    format!("env:{env_name}:config-version")
+}
+
 // Per-environment push notification subscriptions. The suffix is a stable
 // hash of the subscription endpoint URL (see `push::Subscription::id`), so
 // re-POSTing the same subscription is idempotent and listing all
@@ -82,6 +112,73 @@ fn subscription_key(env_name: &EnvName, subscription_id: &str) -> String {
     format!("{}{}", subscription_key_prefix(env_name), subscription_id)
 }
 
+const ORGANIZER_TOKEN_KEY_PREFIX: &str = "organizer-token:";
+
+// Tokens issued to con organizers, keyed by token ID rather than by environment so we can look one
+// up from the bearer token alone. Revoked tokens are kept so we can tell who held them.
+fn organizer_token_key(token_id: &str) -> String {
+    format!("{ORGANIZER_TOKEN_KEY_PREFIX}{token_id}")
+}
+
+// An append-only log of changes made through the admin API. Changes that aren't scoped to an
+// environment, like aliases, go in a global log. Entries are keyed by time so a `prefix` scan
+// returns them in order, and the random suffix keeps entries made in the same millisecond from
+// clobbering each other.
+fn audit_key_prefix(env_name: Option<&EnvName>) -> String {
+    match env_name {
+        Some(env_name) => format!("env:{env_name}:audit:"),
+        None => "audit:".to_string(),
+    }
+}
+
+// Audit entries are kept for this long.
+const AUDIT_ENTRY_TTL: u64 = 60 * 60 * 24 * 365;
+
+fn audit_key(env_name: Option<&EnvName>) -> String {
+    format!(
+        "{}{:013}:{:08x}",
+        audit_key_prefix(env_name),
+        chrono::Utc::now().timestamp_millis(),
+        rand::thread_rng().r#gen::<u32>(),
+    )
+}
+
+// Signatures of signed webhooks we've accepted recently, so a captured request can't be replayed
+// while its timestamp is still fresh. These expire once the timestamp would be rejected anyway.
+fn webhook_signature_key(signature: &str) -> String {
+    format!("webhook-signature:{signature}")
+}
+
+// How often the scheduled worker is currently warming the environment's cache, in seconds, so we
+// know how long a cached value stays fresh.
+fn warm_interval_key(env_name: &EnvName) -> String {
+    format!("env:{env_name}:warm-interval")
+}
+
+// The rows we skipped the last time we refreshed each cached dataset from NocoDB.
+fn validation_report_key_prefix(env_name: &EnvName) -> String {
+    format!("env:{env_name}:validation:")
+}
+
+fn validation_report_key(env_name: &EnvName, dataset: &str) -> String {
+    format!("{}{dataset}", validation_report_key_prefix(env_name))
+}
+
+// How refreshing each cached dataset from NocoDB last went.
+fn cache_status_key_prefix(env_name: &EnvName) -> String {
+    format!("env:{env_name}:cache-status:")
+}
+
+fn cache_status_key(env_name: &EnvName, dataset: &str) -> String {
+    format!("{}{dataset}", cache_status_key_prefix(env_name))
+}
+
+// Which events were in each recent version of the schedule, so clients can sync just the changes.
+// These expire on their own.
+fn events_snapshot_key(env_name: &EnvName, version: &str) -> String {
+    format!("env:{env_name}:events-version:{version}")
+}
+
 fn cache_key_prefix(env_name: &EnvName) -> String {
     format!("env:{env_name}:cache:")
 }
@@ -291,7 +388,9 @@ pub async fn put_api_token(
     env_name: &EnvName,
     api_token: ApiToken,
 ) -> anyhow::Result<()> {
-    kv.put(&api_token_key(env_name), api_token.expose_secret())
+    let encrypted_api_token = config::config_encryption_key().encrypt(api_token.expose_secret())?;
+
+    kv.put(&api_token_key(env_name), encrypted_api_token)
         .map_err(wrap_kv_err)?
         .execute()
         .await
@@ -302,12 +401,14 @@ pub async fn put_api_token(
 
 #[worker::send]
 pub async fn get_api_token(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<Option<ApiToken>> {
-    Ok(kv
-        .get(&api_token_key(env_name))
+    let encryption_key = config::config_encryption_key();
+
+    kv.get(&api_token_key(env_name))
         .text()
         .await
         .map_err(wrap_kv_err)?
-        .map(ApiToken::from))
+        .map(|api_token| encryption_key.decrypt(&api_token).map(ApiToken::from))
+        .transpose()
 }
 
 #[worker::send]
@@ -374,17 +475,35 @@ async fn delete_base_id(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<()>
     Ok(())
 }
 
+// Stored alongside cached values, so we can tell how old they are without decoding them.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheMetadata {
+    cached_at: String,
+}
+
+// Cached values are compressed. See `cache_entry` for the format. This returns the size of the
+// value we stored.
 macro_rules! put_cache_fn {
     ($name:ident, $key_fn:expr, $type:ty) => {
         #[worker::send]
-        pub async fn $name(kv: &KvStore, env_name: &EnvName, value: $type) -> anyhow::Result<()> {
-            kv.put(&$key_fn(env_name), value)
+        pub async fn $name(
+            kv: &KvStore,
+            env_name: &EnvName,
+            value: $type,
+        ) -> anyhow::Result<usize> {
+            let bytes = cache_entry::encode(env_name, value)?;
+
+            kv.put_bytes(&$key_fn(env_name), &bytes)
+                .map_err(wrap_kv_err)?
+                .metadata(CacheMetadata {
+                    cached_at: Utc::now().to_rfc3339(),
+                })
                 .map_err(wrap_kv_err)?
                 .execute()
                 .await
                 .map_err(wrap_kv_err)?;
 
-            Ok(())
+            Ok(bytes.len())
         }
     };
 }
@@ -402,10 +521,26 @@ put_cache_fn!(put_cached_files, files_cache_key, &[File]);
 macro_rules! get_cache_fn {
     ($name:ident, $key_fn:expr, $type:ty) => {
         #[worker::send]
-        pub async fn $name(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<Option<$type>> {
+        pub async fn $name(
+            kv: &KvStore,
+            env_name: &EnvName,
+        ) -> anyhow::Result<Option<CacheEntry<$type>>> {
             let key = $key_fn(env_name);
 
-            kv.get(&key).json::<$type>().await.map_err(wrap_kv_err)
+            let (bytes, metadata) = kv
+                .get(&key)
+                .bytes_with_metadata::<CacheMetadata>()
+                .await
+                .map_err(wrap_kv_err)?;
+
+            // Values we cached before we stored metadata don't have it.
+            let cached_at = metadata.and_then(|metadata| {
+                DateTime::parse_from_rfc3339(&metadata.cached_at)
+                    .ok()
+                    .map(|cached_at| cached_at.to_utc())
+            });
+
+            Ok(bytes.map(|bytes| CacheEntry::from_bytes(bytes).with_cached_at(cached_at)))
         }
     };
 }
@@ -442,38 +577,297 @@ pub async fn delete_cache(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<()
     Ok(())
 }
 
+// This records the new config in the config history before writing it.
 #[worker::send]
 pub async fn put_env_config(
     kv: &KvStore,
     env_name: &EnvName,
     config: &Config,
+) -> anyhow::Result<String> {
+    let encryption_key = config::config_encryption_key();
+    let encrypted_config =
+        config_spec::map_sensitive(config, |value| encryption_key.encrypt(value))?;
+
+    let previous_config = get_env_config(kv, env_name).await?;
+    let version = get_config_version(kv, env_name).await?.unwrap_or(0) + 1;
+
+    let config_version = ConfigVersion {
+        version,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        changes: config_spec::redact_changes(previous_config.diff(config)?),
+        config: encrypted_config.clone(),
+    };
+
+    kv.put(&config_history_key(env_name, version), &config_version)
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    kv.put(&config_version_key(env_name), version.to_string())
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    kv.put(&env_config_key(env_name), &encrypted_config)
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    encrypted_config.etag()
+}
+
+fn decrypt_config_version(config_version: ConfigVersion) -> anyhow::Result<ConfigVersion> {
+    let encryption_key = config::config_encryption_key();
+
+    Ok(ConfigVersion {
+        config: config_spec::map_sensitive(&config_version.config, |value| {
+            encryption_key.decrypt(value)
+        })?,
+        ..config_version
+    })
+}
+
+#[worker::send]
+async fn get_config_version(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<Option<u32>> {
+    kv.get(&config_version_key(env_name))
+        .text()
+        .await
+        .map_err(wrap_kv_err)?
+        .map(|version| version.parse().map_err(anyhow::Error::from))
+        .transpose()
+}
+
+#[worker::send]
+pub async fn get_config_history_version(
+    kv: &KvStore,
+    env_name: &EnvName,
+    version: u32,
+) -> anyhow::Result<Option<ConfigVersion>> {
+    kv.get(&config_history_key(env_name, version))
+        .json::<ConfigVersion>()
+        .await
+        .map_err(wrap_kv_err)?
+        .map(decrypt_config_version)
+        .transpose()
+}
+
+#[worker::send]
+pub async fn list_config_history(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Vec<ConfigVersion>> {
+    let prefix = config_history_key_prefix(env_name);
+    let mut cursor: Option<String> = None;
+    let mut out = Vec::new();
+
+    loop {
+        let mut list = kv.list().prefix(prefix.clone());
+        if let Some(c) = cursor.as_deref() {
+            list = list.cursor(c.to_string());
+        }
+        let page = list.execute().await.map_err(wrap_kv_err)?;
+
+        for key in &page.keys {
+            if let Some(config_version) = kv
+                .get(&key.name)
+                .json::<ConfigVersion>()
+                .await
+                .map_err(wrap_kv_err)?
+            {
+                out.push(decrypt_config_version(config_version)?);
+            }
+        }
+
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(out)
+}
+
+#[worker::send]
+pub async fn put_validation_report(
+    kv: &KvStore,
+    env_name: &EnvName,
+    report: &ValidationReport,
 ) -> anyhow::Result<()> {
-    kv.put(&env_config_key(env_name), config)
+    kv.put(&validation_report_key(env_name, &report.dataset), report)
         .map_err(wrap_kv_err)?
         .execute()
         .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
+
+#[worker::send]
+pub async fn list_validation_reports(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Vec<ValidationReport>> {
+    let prefix = validation_report_key_prefix(env_name);
+    let mut cursor: Option<String> = None;
+    let mut out = Vec::new();
+
+    loop {
+        let mut list = kv.list().prefix(prefix.clone());
+        if let Some(c) = cursor.as_deref() {
+            list = list.cursor(c.to_string());
+        }
+        let page = list.execute().await.map_err(wrap_kv_err)?;
+
+        for key in &page.keys {
+            if let Some(report) = kv
+                .get(&key.name)
+                .json::<ValidationReport>()
+                .await
+                .map_err(wrap_kv_err)?
+            {
+                out.push(report);
+            }
+        }
+
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(out)
+}
+
+#[worker::send]
+pub async fn put_cache_status(
+    kv: &KvStore,
+    env_name: &EnvName,
+    status: &CacheStatus,
+) -> anyhow::Result<()> {
+    kv.put(&cache_status_key(env_name, &status.dataset), status)
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
+
+#[worker::send]
+pub async fn get_cache_status(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+) -> anyhow::Result<Option<CacheStatus>> {
+    kv.get(&cache_status_key(env_name, dataset))
+        .json::<CacheStatus>()
+        .await
+        .map_err(wrap_kv_err)
+}
+
+#[worker::send]
+pub async fn list_cache_statuses(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Vec<CacheStatus>> {
+    // There's one of these per dataset, so we don't have to worry about pagination.
+    let keys = kv
+        .list()
+        .prefix(cache_status_key_prefix(env_name))
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?
+        .keys;
+
+    let mut out = Vec::new();
+
+    for key in keys {
+        if let Some(status) = kv
+            .get(&key.name)
+            .json::<CacheStatus>()
+            .await
+            .map_err(wrap_kv_err)?
+        {
+            out.push(status);
+        }
+    }
+
+    Ok(out)
+}
+
+#[worker::send]
+pub async fn put_events_snapshot(
+    kv: &KvStore,
+    env_name: &EnvName,
+    version: &str,
+    snapshot: &EventsSnapshot,
+    ttl: Duration,
+) -> anyhow::Result<()> {
+    kv.put(&events_snapshot_key(env_name, version), snapshot)
+        .map_err(wrap_kv_err)?
+        .expiration_ttl(ttl.as_secs())
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
+
+#[worker::send]
+pub async fn get_events_snapshot(
+    kv: &KvStore,
+    env_name: &EnvName,
+    version: &str,
+) -> anyhow::Result<Option<EventsSnapshot>> {
+    kv.get(&events_snapshot_key(env_name, version))
+        .json::<EventsSnapshot>()
+        .await
         .map_err(wrap_kv_err)
 }
 
 #[worker::send]
 pub async fn get_env_config(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<Config> {
-    Ok(kv
+    Ok(get_env_config_with_etag(kv, env_name).await?.0)
+}
+
+// The config along with its ETag. The ETag is computed over the config as stored, with sensitive
+// values encrypted.
+#[worker::send]
+pub async fn get_env_config_with_etag(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<(Config, String)> {
+    let encrypted_config = kv
         .get(&env_config_key(env_name))
         .json::<Config>()
         .await
         .map_err(wrap_kv_err)?
-        .unwrap_or_default())
+        .unwrap_or_default();
+
+    let etag = encrypted_config.etag()?;
+
+    let encryption_key = config::config_encryption_key();
+    let config =
+        config_spec::map_sensitive(&encrypted_config, |value| encryption_key.decrypt(value))?;
+
+    Ok((config, etag))
 }
 
+// The metadata also goes on the key so pruning can read it from a `list` alone.
 #[worker::send]
 pub async fn put_subscription(
     kv: &KvStore,
     env_name: &EnvName,
-    subscription: &push::Subscription,
+    record: &push::SubscriptionRecord,
 ) -> anyhow::Result<()> {
-    let key = subscription_key(env_name, &subscription.id());
+    let key = subscription_key(env_name, &record.subscription.id());
 
-    kv.put(&key, subscription)
+    kv.put(&key, record)
+        .map_err(wrap_kv_err)?
+        .metadata(&record.metadata)
         .map_err(wrap_kv_err)?
         .execute()
         .await
@@ -482,6 +876,18 @@ pub async fn put_subscription(
     Ok(())
 }
 
+#[worker::send]
+pub async fn get_subscription(
+    kv: &KvStore,
+    env_name: &EnvName,
+    subscription_id: &str,
+) -> anyhow::Result<Option<push::SubscriptionRecord>> {
+    kv.get(&subscription_key(env_name, subscription_id))
+        .json::<push::SubscriptionRecord>()
+        .await
+        .map_err(wrap_kv_err)
+}
+
 #[worker::send]
 pub async fn delete_subscription(
     kv: &KvStore,
@@ -501,11 +907,10 @@ pub async fn delete_subscription(
 /// could plausibly cross 1000, so we handle the cursor properly even though
 /// every other `kv::list` caller in this codebase stops at one page.
 #[worker::send]
-#[allow(dead_code)] // consumed by the webhook fan-out in slice 3
 pub async fn list_subscriptions(
     kv: &KvStore,
     env_name: &EnvName,
-) -> anyhow::Result<Vec<push::Subscription>> {
+) -> anyhow::Result<Vec<push::SubscriptionRecord>> {
     let prefix = subscription_key_prefix(env_name);
     let mut cursor: Option<String> = None;
     let mut out = Vec::new();
@@ -518,13 +923,13 @@ pub async fn list_subscriptions(
         let page = list.execute().await.map_err(wrap_kv_err)?;
 
         for key in &page.keys {
-            if let Some(subscription) = kv
+            if let Some(record) = kv
                 .get(&key.name)
-                .json::<push::Subscription>()
+                .json::<push::SubscriptionRecord>()
                 .await
                 .map_err(wrap_kv_err)?
             {
-                out.push(subscription);
+                out.push(record);
             }
             // If the key vanished between `list` and `get`, just skip it —
             // a concurrent DELETE on the same subscription is benign.
@@ -538,3 +943,256 @@ pub async fn list_subscriptions(
 
     Ok(out)
 }
+
+/// List the ID and metadata of every subscription in this environment
+/// without reading the subscriptions themselves. Subscriptions stored before
+/// we tracked metadata come back with it empty.
+#[worker::send]
+pub async fn list_subscription_metadata(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Vec<(String, push::SubscriptionMetadata)>> {
+    let prefix = subscription_key_prefix(env_name);
+    let mut cursor: Option<String> = None;
+    let mut out = Vec::new();
+
+    loop {
+        let mut list = kv.list().prefix(prefix.clone());
+        if let Some(c) = cursor.as_deref() {
+            list = list.cursor(c.to_string());
+        }
+        let page = list.execute().await.map_err(wrap_kv_err)?;
+
+        for key in page.keys {
+            let Some(subscription_id) = key.name.strip_prefix(&prefix) else {
+                continue;
+            };
+
+            let metadata = key
+                .metadata
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+
+            out.push((subscription_id.to_string(), metadata));
+        }
+
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(out)
+}
+
+/// Every environment which currently has an app link. This is for jobs that
+/// run across all environments.
+#[worker::send]
+pub async fn list_env_names(kv: &KvStore) -> anyhow::Result<Vec<EnvName>> {
+    let mut cursor: Option<String> = None;
+    let mut out: Vec<EnvName> = Vec::new();
+
+    loop {
+        let mut list = kv.list().prefix(ID_ENV_KEY_PREFIX.to_string());
+        if let Some(c) = cursor.as_deref() {
+            list = list.cursor(c.to_string());
+        }
+        let page = list.execute().await.map_err(wrap_kv_err)?;
+
+        for key in &page.keys {
+            let Some(env_id) = key
+                .name
+                .strip_prefix(ID_ENV_KEY_PREFIX)
+                .and_then(|key| key.strip_suffix(ID_ENV_KEY_SUFFIX))
+            else {
+                continue;
+            };
+
+            // Old app links still map to their environment, so skip those.
+            if let Some(env_name) = get_id_env(kv, &EnvId::from(env_id.to_string())).await?
+                && !out
+                    .iter()
+                    .any(|existing| existing.to_string() == env_name.to_string())
+            {
+                out.push(env_name);
+            }
+        }
+
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(out)
+}
+
+#[worker::send]
+pub async fn put_organizer_token(kv: &KvStore, token: &OrganizerToken) -> anyhow::Result<()> {
+    kv.put(&organizer_token_key(&token.id), token)
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
+
+#[worker::send]
+pub async fn get_organizer_token(
+    kv: &KvStore,
+    token_id: &str,
+) -> anyhow::Result<Option<OrganizerToken>> {
+    kv.get(&organizer_token_key(token_id))
+        .json::<OrganizerToken>()
+        .await
+        .map_err(wrap_kv_err)
+}
+
+#[worker::send]
+pub async fn list_organizer_tokens(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Vec<OrganizerToken>> {
+    let mut cursor: Option<String> = None;
+    let mut out = Vec::new();
+
+    loop {
+        let mut list = kv.list().prefix(ORGANIZER_TOKEN_KEY_PREFIX.to_string());
+        if let Some(c) = cursor.as_deref() {
+            list = list.cursor(c.to_string());
+        }
+        let page = list.execute().await.map_err(wrap_kv_err)?;
+
+        for key in &page.keys {
+            if let Some(token) = kv
+                .get(&key.name)
+                .json::<OrganizerToken>()
+                .await
+                .map_err(wrap_kv_err)?
+                && token.env_name.to_string() == env_name.to_string()
+            {
+                out.push(token);
+            }
+        }
+
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => break,
+        }
+    }
+
+    Ok(out)
+}
+
+// The entry also goes in the key metadata so listing entries doesn't need a read per entry.
+#[worker::send]
+pub async fn put_audit_entry(
+    kv: &KvStore,
+    env_name: Option<&EnvName>,
+    entry: &AuditEntry,
+) -> anyhow::Result<()> {
+    kv.put(&audit_key(env_name), entry)
+        .map_err(wrap_kv_err)?
+        .metadata(entry)
+        .map_err(wrap_kv_err)?
+        .expiration_ttl(AUDIT_ENTRY_TTL)
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
+
+// One page of audit entries, oldest first, and the cursor for the next page if there is one.
+#[worker::send]
+pub async fn list_audit_entries(
+    kv: &KvStore,
+    env_name: Option<&EnvName>,
+    limit: u64,
+    cursor: Option<String>,
+) -> anyhow::Result<(Vec<AuditEntry>, Option<String>)> {
+    let mut list = kv.list().prefix(audit_key_prefix(env_name)).limit(limit);
+    if let Some(cursor) = cursor {
+        list = list.cursor(cursor);
+    }
+    let page = list.execute().await.map_err(wrap_kv_err)?;
+
+    let mut entries = Vec::with_capacity(page.keys.len());
+
+    for key in page.keys {
+        match key.metadata {
+            Some(metadata) => entries.push(serde_json::from_value(metadata)?),
+            // Entries we recorded before we stored them in the key metadata.
+            None => {
+                if let Some(entry) = kv
+                    .get(&key.name)
+                    .json::<AuditEntry>()
+                    .await
+                    .map_err(wrap_kv_err)?
+                {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+
+    let cursor = page.cursor.filter(|_| !page.list_complete);
+
+    Ok((entries, cursor))
+}
+
+// This returns `false` if we've already seen this signature. KV is eventually consistent, so this
+// won't catch a replay that lands on another edge location within a few seconds, but the signature
+// must still be fresh.
+#[worker::send]
+pub async fn put_webhook_signature(
+    kv: &KvStore,
+    signature: &str,
+    ttl: Duration,
+) -> anyhow::Result<bool> {
+    let key = webhook_signature_key(signature);
+
+    if kv.get(&key).text().await.map_err(wrap_kv_err)?.is_some() {
+        return Ok(false);
+    }
+
+    kv.put(&key, "")
+        .map_err(wrap_kv_err)?
+        .expiration_ttl(ttl.as_secs())
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(true)
+}
+
+#[worker::send]
+pub async fn get_warm_interval(
+    kv: &KvStore,
+    env_name: &EnvName,
+) -> anyhow::Result<Option<Duration>> {
+    Ok(kv
+        .get(&warm_interval_key(env_name))
+        .text()
+        .await
+        .map_err(wrap_kv_err)?
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .map(Duration::from_secs))
+}
+
+#[worker::send]
+pub async fn put_warm_interval(
+    kv: &KvStore,
+    env_name: &EnvName,
+    interval: Duration,
+) -> anyhow::Result<()> {
+    kv.put(&warm_interval_key(env_name), interval.as_secs().to_string())
+        .map_err(wrap_kv_err)?
+        .execute()
+        .await
+        .map_err(wrap_kv_err)?;
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Leases that stop several isolates from refreshing the same cache key from NocoDB at once. Each
+//! environment and cache key has a Durable Object that hands out the lease. A Durable Object
+//! handles one request at a time, so unlike a lease in KV, two isolates can't both take it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use worker::{
+    DurableObject, Env, Headers, Method, ObjectNamespace, Request, RequestInit, Response, State,
+    Stub, durable_object,
+};
+
+use crate::env::EnvName;
+
+/// The name of the Durable Object binding in `wrangler.toml`.
+pub const REFRESH_LEASES_BINDING: &str = "REFRESH_LEASES";
+
+// Requests from the worker to the Durable Object never leave Cloudflare, so the host is unused.
+const ACQUIRE_URL: &str = "https://leases/acquire";
+const RELEASE_URL: &str = "https://leases/release";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseRequest {
+    colo: String,
+    ttl_secs: u64,
+}
+
+fn stub(namespace: &ObjectNamespace, env_name: &EnvName, cache_key: &str) -> worker::Result<Stub> {
+    namespace
+        .id_from_name(&format!("{env_name}:{cache_key}"))?
+        .get_stub()
+}
+
+async fn send(
+    namespace: &ObjectNamespace,
+    env_name: &EnvName,
+    cache_key: &str,
+    url: &str,
+    lease: &LeaseRequest,
+) -> worker::Result<Response> {
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+
+    let req = Request::new_with_init(
+        url,
+        &RequestInit {
+            method: Method::Post,
+            headers,
+            body: Some(serde_json::to_string(lease)?.into()),
+            ..Default::default()
+        },
+    )?;
+
+    stub(namespace, env_name, cache_key)?
+        .fetch_with_request(req)
+        .await
+}
+
+/// Try to take the refresh lease for a cache key in a datacenter. This returns `false` if another
+/// isolate holds it. The lease expires on its own in case the isolate holding it dies before
+/// releasing it.
+///
+/// Leases are per datacenter because the edge cache is, so each datacenter needs its own refresh.
+#[worker::send]
+pub async fn acquire(
+    namespace: &ObjectNamespace,
+    env_name: &EnvName,
+    colo: &str,
+    cache_key: &str,
+    ttl: Duration,
+) -> worker::Result<bool> {
+    let lease = LeaseRequest {
+        colo: colo.to_string(),
+        ttl_secs: ttl.as_secs(),
+    };
+
+    send(namespace, env_name, cache_key, ACQUIRE_URL, &lease)
+        .await?
+        .json::<bool>()
+        .await
+}
+
+#[worker::send]
+pub async fn release(
+    namespace: &ObjectNamespace,
+    env_name: &EnvName,
+    colo: &str,
+    cache_key: &str,
+) -> worker::Result<()> {
+    let lease = LeaseRequest {
+        colo: colo.to_string(),
+        ttl_secs: 0,
+    };
+
+    send(namespace, env_name, cache_key, RELEASE_URL, &lease).await?;
+
+    Ok(())
+}
+
+// When each datacenter's lease expires.
+#[derive(Debug, Default)]
+struct Leases(HashMap<String, DateTime<Utc>>);
+
+impl Leases {
+    fn acquire(&mut self, colo: &str, ttl: Duration, now: DateTime<Utc>) -> bool {
+        if self.0.get(colo).is_some_and(|expires_at| now < *expires_at) {
+            return false;
+        }
+
+        let ttl = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        self.0.insert(colo.to_string(), now + ttl);
+
+        true
+    }
+
+    fn release(&mut self, colo: &str) {
+        self.0.remove(colo);
+    }
+}
+
+/// Hands out the refresh leases for one environment and cache key. Leases are only kept in
+/// memory, so if the object is evicted while a refresh is running, another isolate may start a
+/// second one. That's rare and only costs one extra request to NocoDB.
+#[durable_object]
+pub struct RefreshLeases {
+    leases: RefCell<Leases>,
+}
+
+impl DurableObject for RefreshLeases {
+    fn new(_state: State, _env: Env) -> Self {
+        Self {
+            leases: RefCell::new(Leases::default()),
+        }
+    }
+
+    async fn fetch(&self, mut req: Request) -> worker::Result<Response> {
+        let path = req.path();
+        let lease = req.json::<LeaseRequest>().await?;
+
+        match path.as_str() {
+            "/acquire" => {
+                let acquired = self.leases.borrow_mut().acquire(
+                    &lease.colo,
+                    Duration::from_secs(lease.ttl_secs),
+                    Utc::now(),
+                );
+                Response::from_json(&acquired)
+            }
+            "/release" => {
+                self.leases.borrow_mut().release(&lease.colo);
+                Ok(Response::empty()?.with_status(204))
+            }
+            _ => Response::error("Not Found", 404),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn only_one_isolate_holds_a_lease_until_it_expires() {
+        let mut leases = Leases::default();
+        let now = Utc::now();
+
+        assert!(leases.acquire("SEA", TTL, now));
+        assert!(!leases.acquire("SEA", TTL, now));
+        assert!(leases.acquire("LHR", TTL, now));
+
+        assert!(leases.acquire("SEA", TTL, now + TTL));
+    }
+
+    #[test]
+    fn a_released_lease_can_be_taken_again() {
+        let mut leases = Leases::default();
+        let now = Utc::now();
+
+        assert!(leases.acquire("SEA", TTL, now));
+        leases.release("SEA");
+        assert!(leases.acquire("SEA", TTL, now));
+    }
+}
@@ -1,20 +1,30 @@
 mod api;
+mod audit;
 mod auth;
 mod cache;
+mod cache_entry;
 mod cf;
+mod changes;
 mod config;
+mod config_spec;
 mod cors;
+mod crypto;
 mod env;
 mod error;
 mod http;
 mod kv;
+mod lease;
+mod live;
 mod neon;
 mod noco;
 mod push;
+mod query;
+mod rate_limit;
 mod router;
 mod sql;
 mod store;
 mod url;
+mod warm;
 
 use std::sync::Arc;
 
@@ -36,7 +46,52 @@ async fn fetch(
         kv: env.kv("KV")?,
         bucket: SendWrapper(env.bucket("ASSETS_BUCKET")?),
         ctx: Arc::new(ctx),
+        colo: req.extensions().get::<Cf>().map(Cf::colo),
+        live: env.durable_object(live::LIVE_UPDATES_BINDING)?,
+        leases: env.durable_object(lease::REFRESH_LEASES_BINDING)?,
     };
 
     Ok(router::new(state).call(req).await?)
 }
+
+async fn prune_subscriptions(kv: &worker::kv::KvStore) {
+    let env_names = match kv::list_env_names(kv).await {
+        Ok(env_names) => env_names,
+        Err(err) => {
+            console_error!("Failed to list environments: {err}");
+            return;
+        }
+    };
+
+    // One environment failing shouldn't stop the others from being pruned.
+    for env_name in env_names {
+        if let Err(err) = push::prune_subscriptions(kv, &env_name).await {
+            console_error!("Failed to prune push subscriptions for {env_name}: {err}");
+        }
+    }
+}
+
+// This runs on the cron triggers in `wrangler.toml`.
+#[event(scheduled)]
+async fn scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    console_error_panic_hook::set_once();
+
+    config::init(&env).expect("failed to initialize config");
+
+    let kv = env.kv("KV").expect("failed to get KV binding");
+
+    if event.cron() == warm::WARM_CRON {
+        let live = env
+            .durable_object(live::LIVE_UPDATES_BINDING)
+            .expect("failed to get live updates binding");
+
+        let scheduled_at = chrono::DateTime::from_timestamp_millis(event.schedule() as i64)
+            .unwrap_or_else(chrono::Utc::now);
+
+        if let Err(err) = warm::warm_caches(&kv, &live, scheduled_at).await {
+            console_error!("Failed to warm caches: {err}");
+        }
+    } else {
+        prune_subscriptions(&kv).await;
+    }
+}
@@ -0,0 +1,253 @@
+//! Live updates for clients with the app open, over Server-Sent Events. Each environment has a
+//! Durable Object that holds its open streams, and we tell it whenever we refresh cached data, so
+//! clients can refetch a section as soon as it changes rather than waiting to poll.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::{StreamExt, channel::mpsc};
+use serde::{Deserialize, Serialize};
+use worker::{
+    DurableObject, Env, Headers, Method, ObjectNamespace, Request, RequestInit, Response, State,
+    Stub, console_warn, durable_object,
+};
+
+use crate::{api::SectionBody, cache::content_hash, env::EnvName, noco};
+
+/// The name of the Durable Object binding in `wrangler.toml`.
+pub const LIVE_UPDATES_BINDING: &str = "LIVE_UPDATES";
+
+/// Intermediaries may close a stream that's quiet for too long, so we send a comment this often.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long the browser should wait before reconnecting a dropped stream.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Requests from the worker to the Durable Object never leave Cloudflare, so the host is unused.
+const CONNECT_URL: &str = "https://live/connect";
+const PUBLISH_URL: &str = "https://live/publish";
+
+/// A section of the app's data, named after the endpoint that serves it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    Events,
+    Info,
+    Pages,
+    Announcements,
+    Files,
+}
+
+/// Cached data whose changes we tell live clients about.
+pub trait LiveSection: SectionBody {
+    const SECTION: Section;
+
+    /// The ETag the section's endpoint returns for this data without any query params. Filtered
+    /// or projected responses have their own ETags, which never match this.
+    fn etag(&self, env_name: &EnvName) -> String {
+        format!("W/\"{}\"", content_hash(&self.section_body(env_name)))
+    }
+}
+
+impl LiveSection for Vec<noco::Event> {
+    const SECTION: Section = Section::Events;
+}
+
+impl LiveSection for noco::Info {
+    const SECTION: Section = Section::Info;
+}
+
+impl LiveSection for Vec<noco::Page> {
+    const SECTION: Section = Section::Pages;
+}
+
+impl LiveSection for Vec<noco::Announcement> {
+    const SECTION: Section = Section::Announcements;
+}
+
+impl LiveSection for Vec<noco::File> {
+    const SECTION: Section = Section::Files;
+}
+
+/// Tells clients that a section now has a new ETag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveMessage {
+    pub section: Section,
+    pub etag: String,
+}
+
+impl LiveMessage {
+    pub fn of<T: LiveSection>(env_name: &EnvName, value: &T) -> Self {
+        Self {
+            section: T::SECTION,
+            etag: value.etag(env_name),
+        }
+    }
+
+    fn to_event(&self) -> String {
+        // Serializing this can't fail.
+        let data = serde_json::to_string(self).unwrap_or_default();
+        format!("event: changed\ndata: {data}\n\n")
+    }
+}
+
+fn stub(namespace: &ObjectNamespace, env_name: &EnvName) -> worker::Result<Stub> {
+    namespace.id_from_name(&env_name.to_string())?.get_stub()
+}
+
+/// Open a live update stream for an environment.
+pub async fn connect(namespace: &ObjectNamespace, env_name: &EnvName) -> worker::Result<Response> {
+    stub(namespace, env_name)?.fetch_with_str(CONNECT_URL).await
+}
+
+async fn publish(
+    namespace: &ObjectNamespace,
+    env_name: &EnvName,
+    messages: &[LiveMessage],
+) -> worker::Result<()> {
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+
+    let req = Request::new_with_init(
+        PUBLISH_URL,
+        &RequestInit {
+            method: Method::Post,
+            headers,
+            body: Some(serde_json::to_string(messages)?.into()),
+            ..Default::default()
+        },
+    )?;
+
+    stub(namespace, env_name)?.fetch_with_request(req).await?;
+
+    Ok(())
+}
+
+/// Tell the clients connected to an environment that these sections may have changed. The
+/// Durable Object only passes on the ones whose ETag actually changed. Live updates are best
+/// effort, so this doesn't fail; clients still poll.
+pub async fn notify(namespace: &ObjectNamespace, env_name: &EnvName, messages: &[LiveMessage]) {
+    if messages.is_empty() {
+        return;
+    }
+
+    if let Err(e) = publish(namespace, env_name, messages).await {
+        console_warn!("Failed sending live updates for {}: {}", env_name, e);
+    }
+}
+
+/// Holds the open live update streams for one environment.
+#[durable_object]
+pub struct LiveUpdates {
+    state: State,
+    clients: RefCell<Vec<mpsc::UnboundedSender<String>>>,
+    // The last ETag we sent for each section. This is only in memory, so after the object is
+    // evicted, the first update for each section is sent even if it didn't change.
+    etags: RefCell<HashMap<Section, String>>,
+}
+
+impl LiveUpdates {
+    /// Send to every open stream, and forget the ones that have closed.
+    fn broadcast(&self, text: &str) {
+        self.clients
+            .borrow_mut()
+            .retain(|client| client.unbounded_send(text.to_string()).is_ok());
+    }
+
+    fn publish(&self, messages: Vec<LiveMessage>) {
+        for message in messages {
+            let changed = self
+                .etags
+                .borrow_mut()
+                .insert(message.section, message.etag.clone())
+                .as_ref()
+                != Some(&message.etag);
+
+            if changed {
+                self.broadcast(&message.to_event());
+            }
+        }
+    }
+
+    async fn connect(&self) -> worker::Result<Response> {
+        let (client, stream) = mpsc::unbounded::<String>();
+
+        // Send the ETags we know about up front, so a client that reconnects can tell whether it
+        // missed anything while it was gone.
+        let mut greeting = format!("retry: {}\n\n", RECONNECT_DELAY.as_millis());
+        for (section, etag) in self.etags.borrow().iter() {
+            greeting.push_str(
+                &LiveMessage {
+                    section: *section,
+                    etag: etag.clone(),
+                }
+                .to_event(),
+            );
+        }
+        client.unbounded_send(greeting).ok();
+
+        self.clients.borrow_mut().push(client);
+
+        let storage = self.state.storage();
+        if storage.get_alarm().await?.is_none() {
+            storage.set_alarm(KEEPALIVE_INTERVAL).await?;
+        }
+
+        let headers = Headers::new();
+        headers.set("Content-Type", "text/event-stream")?;
+        headers.set("Cache-Control", "no-cache")?;
+
+        Ok(Response::from_stream(stream.map(Ok::<_, worker::Error>))?.with_headers(headers))
+    }
+}
+
+impl DurableObject for LiveUpdates {
+    fn new(state: State, _env: Env) -> Self {
+        Self {
+            state,
+            clients: RefCell::new(Vec::new()),
+            etags: RefCell::new(HashMap::new()),
+        }
+    }
+
+    async fn fetch(&self, mut req: Request) -> worker::Result<Response> {
+        match req.path().as_str() {
+            "/connect" => self.connect().await,
+            "/publish" => {
+                self.publish(req.json::<Vec<LiveMessage>>().await?);
+                Ok(Response::empty()?.with_status(204))
+            }
+            _ => Response::error("Not Found", 404),
+        }
+    }
+
+    async fn alarm(&self) -> worker::Result<Response> {
+        self.broadcast(": keepalive\n\n");
+
+        // The alarm only keeps running while someone is listening.
+        if !self.clients.borrow().is_empty() {
+            self.state.storage().set_alarm(KEEPALIVE_INTERVAL).await?;
+        }
+
+        Response::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_server_sent_events() {
+        let message = LiveMessage {
+            section: Section::Events,
+            etag: "W/\"abc\"".to_string(),
+        };
+
+        assert_eq!(
+            message.to_event(),
+            "event: changed\ndata: {\"section\":\"events\",\"etag\":\"W/\\\"abc\\\"\"}\n\n"
+        );
+    }
+}
@@ -16,18 +16,69 @@ struct PageInfo {
     is_last_page: bool,
 }
 
-async fn list_records<T: DeserializeOwned>(
+// A row we had to skip because it doesn't match what we expect, so organizers can find and fix it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowIssue {
+    pub table: String,
+    pub row_id: Option<String>,
+    pub problem: String,
+}
+
+impl RowIssue {
+    fn new(table: &str, row_id: Option<String>, problem: impl Into<String>) -> Self {
+        Self {
+            table: table.to_string(),
+            row_id,
+            problem: problem.into(),
+        }
+    }
+}
+
+// The rows we skipped the last time we refreshed one of the cached datasets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub dataset: String,
+    pub checked_at: String,
+    pub issues: Vec<RowIssue>,
+}
+
+// Data built from NocoDB, along with the rows we skipped to build it.
+#[derive(Debug)]
+pub struct Validated<T> {
+    pub value: T,
+    pub issues: Vec<RowIssue>,
+}
+
+pub(super) struct Records<T> {
+    pub records: Vec<T>,
+    pub issues: Vec<RowIssue>,
+}
+
+fn row_id(record: &serde_json::Value) -> Option<String> {
+    match record.get("ID")? {
+        serde_json::Value::String(id) => Some(id.clone()),
+        serde_json::Value::Null => None,
+        id => Some(id.to_string()),
+    }
+}
+
+// We parse each record on its own, so one malformed row doesn't stop us from caching the rest.
+// However, if every row fails, it's more likely that a column was renamed or removed than that
+// every row is wrong, so we fail and keep serving the data we already have.
+pub(super) async fn list_records<T: DeserializeOwned>(
     client: &Client,
+    table: &str,
     table_id: &TableId,
-) -> anyhow::Result<Vec<T>> {
+) -> anyhow::Result<Records<T>> {
     #[derive(Debug, Deserialize)]
-    struct GetRecordsResponse<T> {
-        list: Vec<T>,
+    struct GetRecordsResponse {
+        list: Vec<serde_json::Value>,
         #[serde(rename = "pageInfo")]
         page_info: PageInfo,
     }
 
     let mut records = Vec::<T>::new();
+    let mut issues = Vec::<RowIssue>::new();
     let mut offset = 0;
 
     loop {
@@ -35,34 +86,51 @@ async fn list_records<T: DeserializeOwned>(
             .build_request_v2(Method::Get, &format!("/tables/{table_id}/records"))
             .with_param("limit", &PAGE_SIZE.to_string())
             .with_param("offset", &offset.to_string())
-            .fetch::<GetRecordsResponse<T>>()
+            .fetch::<GetRecordsResponse>()
             .await?;
 
         offset += response.list.len();
-        records.extend(response.list);
+
+        for record in response.list {
+            let id = row_id(&record);
+            match serde_json::from_value::<T>(record) {
+                Ok(record) => records.push(record),
+                Err(e) => issues.push(RowIssue::new(table, id, e.to_string())),
+            }
+        }
 
         if response.page_info.is_last_page {
             break;
         }
     }
 
-    Ok(records)
+    if records.is_empty()
+        && let Some(issue) = issues.first()
+    {
+        anyhow::bail!(
+            "Every row in the {table} table is invalid. The first problem was in row {}: {}",
+            issue.row_id.as_deref().unwrap_or("(unknown)"),
+            issue.problem,
+        );
+    }
+
+    Ok(Records { records, issues })
 }
 
 #[derive(Debug, Deserialize)]
-struct LocationResponse {
+pub(super) struct LocationResponse {
     #[serde(rename = "Location")]
     pub name: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct CategoryResponse {
+pub(super) struct CategoryResponse {
     #[serde(rename = "Category")]
     pub name: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct EventResponse {
+pub(super) struct EventResponse {
     #[serde(rename = "ID")]
     pub id: u32,
     #[serde(rename = "Event Name")]
@@ -88,19 +156,19 @@ struct EventResponse {
 }
 
 #[derive(Debug, Deserialize)]
-struct PeopleM2mResponse {
+pub(super) struct PeopleM2mResponse {
     #[serde(rename = "people_id")]
     pub id: u32,
 }
 
 #[derive(Debug, Deserialize)]
-struct TagsM2mResponse {
+pub(super) struct TagsM2mResponse {
     #[serde(rename = "tags_id")]
     pub id: u32,
 }
 
 #[derive(Debug, Deserialize)]
-struct PeopleResponse {
+pub(super) struct PeopleResponse {
     #[serde(rename = "ID")]
     pub id: u32,
     #[serde(rename = "Name")]
@@ -108,7 +176,7 @@ struct PeopleResponse {
 }
 
 #[derive(Debug, Deserialize)]
-struct TagResponse {
+pub(super) struct TagResponse {
     #[serde(rename = "ID")]
     pub id: u32,
     #[serde(rename = "Tag")]
@@ -235,54 +303,88 @@ pub struct Announcement {
     pub updated_at: Option<String>,
 }
 
+// Check the times of an event we'd otherwise show. This returns `None` for an event that doesn't
+// belong in the schedule at all.
+pub(super) fn check_event_times(event: &EventResponse) -> Option<Result<(), String>> {
+    // We allow event organizers to create events in NocoDB without a start time to give them more
+    // flexibility in how they plan the schedule. However, events without a start time will not be
+    // returned to the client, because it's not obvious how the client should display them in the
+    // schedule view.
+    let start_time = event.start_time.as_deref()?;
+
+    let Ok(start_time) = DateTime::parse_from_rfc3339(start_time) else {
+        return Some(Err(format!(
+            "start time {start_time:?} is not a valid time"
+        )));
+    };
+
+    let Some(end_time) = event.end_time.as_deref() else {
+        return Some(Ok(()));
+    };
+
+    let Ok(end_time) = DateTime::parse_from_rfc3339(end_time) else {
+        return Some(Err(format!("end time {end_time:?} is not a valid time")));
+    };
+
+    // Similarly, we filter out events where the end time comes before the start time, because
+    // it's not obvious how the client should display them.
+    if end_time < start_time {
+        return Some(Err("the end time is before the start time".to_string()));
+    }
+
+    Some(Ok(()))
+}
+
 #[worker::send]
-pub async fn get_events(client: &Client, table_ids: &TableIds) -> anyhow::Result<Vec<Event>> {
+pub async fn get_events(
+    client: &Client,
+    table_ids: &TableIds,
+) -> anyhow::Result<Validated<Vec<Event>>> {
     let (event_records_result, people_records_result, tags_records_result) = futures::join!(
-        list_records::<EventResponse>(client, &table_ids.events),
-        list_records::<PeopleResponse>(client, &table_ids.people),
-        list_records::<TagResponse>(client, &table_ids.tags),
+        list_records::<EventResponse>(client, "events", &table_ids.events),
+        list_records::<PeopleResponse>(client, "people", &table_ids.people),
+        list_records::<TagResponse>(client, "tags", &table_ids.tags),
     );
 
-    let people_id_to_name: HashMap<u32, String> = people_records_result?
+    let event_records = event_records_result?;
+    let people_records = people_records_result?;
+    let tags_records = tags_records_result?;
+
+    let people_id_to_name: HashMap<u32, String> = people_records
+        .records
         .iter()
         .map(|p| (p.id, p.name.clone()))
         .collect();
-    let tags_id_to_name: HashMap<u32, String> = tags_records_result?
+    let tags_id_to_name: HashMap<u32, String> = tags_records
+        .records
         .iter()
         .map(|p| (p.id, p.name.clone()))
         .collect();
 
-    let mut events = event_records_result?
-        .into_iter()
-        .filter(|r| !r.hidden)
-        // We allow event organizers to create events in NocoDB without a start time to give them
-        // more flexibility in how they plan the schedule. However, events without a start time
-        // will not be returned to the client, because it's not obvious how the client should
-        // display them in the schedule view.
-        //
-        // Similarly, we filter out events where the end time comes before the start time, because
-        // it's not obvious how the client should display them.
-        .filter(|r| match r {
-            EventResponse {
-                start_time: Some(start_time),
-                end_time: Some(end_time),
-                ..
-            } => match (
-                DateTime::parse_from_rfc3339(start_time),
-                DateTime::parse_from_rfc3339(end_time),
-            ) {
-                (Ok(start_time), Ok(end_time)) => start_time <= end_time,
-                _ => false,
-            },
-            EventResponse {
-                start_time: Some(_),
-                ..
-            } => true,
-            EventResponse {
-                start_time: None, ..
-            } => false,
-        })
-        .map(|r| Event {
+    let mut issues = [
+        event_records.issues,
+        people_records.issues,
+        tags_records.issues,
+    ]
+    .concat();
+
+    let mut events = Vec::with_capacity(event_records.records.len());
+
+    for r in event_records.records {
+        if r.hidden {
+            continue;
+        }
+
+        match check_event_times(&r) {
+            None => continue,
+            Some(Err(problem)) => {
+                issues.push(RowIssue::new("events", Some(r.id.to_string()), problem));
+                continue;
+            }
+            Some(Ok(())) => {}
+        }
+
+        events.push(Event {
             id: r.id.to_string(),
             name: r.name,
             summary: r.summary,
@@ -301,161 +403,229 @@ pub async fn get_events(client: &Client, table_ids: &TableIds) -> anyhow::Result
                 .into_iter()
                 .filter_map(|p| tags_id_to_name.get(&p.id).cloned())
                 .collect(),
-        })
-        .collect::<Vec<_>>();
+        });
+    }
 
     // Sort events by start time, then end time.
     events.sort_by(|a, b| a.end_time.cmp(&b.end_time));
     events.sort_by(|a, b| a.start_time.cmp(&b.start_time));
 
-    Ok(events)
+    Ok(Validated {
+        value: events,
+        issues,
+    })
 }
 
-pub async fn get_about(client: &Client, table_ids: &TableIds) -> anyhow::Result<About> {
-    let about_records = list_records::<AboutResponse>(client, &table_ids.about).await?;
-    let latest_record = about_records.into_iter().next_back();
-
-    Ok(latest_record
-        .map(|r| About {
-            name: Some(r.name),
-            description: r.description,
-            website_url: r.website_url,
-            files: r
-                .files
-                .unwrap_or_default()
-                .into_iter()
-                .map(|f| File {
-                    id: f.id,
-                    name: f.title,
-                    media_type: f.media_type,
-                    signed_url: f.signed_url,
-                })
-                .collect(),
+fn to_files(files: Option<Vec<FileBodyResponse>>) -> Vec<File> {
+    files
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| File {
+            id: f.id,
+            name: f.title,
+            media_type: f.media_type,
+            signed_url: f.signed_url,
         })
-        .unwrap_or_default())
+        .collect()
 }
 
-async fn get_links(client: &Client, table_ids: &TableIds) -> anyhow::Result<Vec<Link>> {
-    let link_records = list_records::<LinkResponse>(client, &table_ids.links).await?;
+async fn get_about(client: &Client, table_ids: &TableIds) -> anyhow::Result<Validated<About>> {
+    let about_records = list_records::<AboutResponse>(client, "about", &table_ids.about).await?;
+    let latest_record = about_records.records.into_iter().next_back();
+
+    Ok(Validated {
+        value: latest_record
+            .map(|r| About {
+                name: Some(r.name),
+                description: r.description,
+                website_url: r.website_url,
+                files: to_files(r.files),
+            })
+            .unwrap_or_default(),
+        issues: about_records.issues,
+    })
+}
 
-    Ok(link_records
-        .into_iter()
-        .map(|r| Link {
-            name: r.name,
-            url: r.url,
-        })
-        .collect())
+async fn get_links(client: &Client, table_ids: &TableIds) -> anyhow::Result<Validated<Vec<Link>>> {
+    let link_records = list_records::<LinkResponse>(client, "links", &table_ids.links).await?;
+
+    Ok(Validated {
+        value: link_records
+            .records
+            .into_iter()
+            .map(|r| Link {
+                name: r.name,
+                url: r.url,
+            })
+            .collect(),
+        issues: link_records.issues,
+    })
 }
 
 #[worker::send]
-pub async fn get_info(client: &Client, table_ids: &TableIds) -> anyhow::Result<Info> {
+pub async fn get_info(client: &Client, table_ids: &TableIds) -> anyhow::Result<Validated<Info>> {
     let about = get_about(client, table_ids).await?;
     let links = get_links(client, table_ids).await?;
 
-    Ok(Info { about, links })
+    Ok(Validated {
+        value: Info {
+            about: about.value,
+            links: links.value,
+        },
+        issues: [about.issues, links.issues].concat(),
+    })
 }
 
 #[worker::send]
-pub async fn get_pages(client: &Client, table_ids: &TableIds) -> anyhow::Result<Vec<Page>> {
-    let page_records = list_records::<PageResponse>(client, &table_ids.pages).await?;
-
-    Ok(page_records
-        .into_iter()
-        .map(|r| Page {
-            id: r.id.to_string(),
-            title: r.title,
-            body: r.body.unwrap_or_default(),
-            files: r
-                .files
-                .unwrap_or_default()
-                .into_iter()
-                .map(|f| File {
-                    id: f.id,
-                    name: f.title,
-                    media_type: f.media_type,
-                    signed_url: f.signed_url,
-                })
-                .collect(),
-        })
-        .collect())
+pub async fn get_pages(
+    client: &Client,
+    table_ids: &TableIds,
+) -> anyhow::Result<Validated<Vec<Page>>> {
+    let page_records = list_records::<PageResponse>(client, "pages", &table_ids.pages).await?;
+
+    Ok(Validated {
+        value: page_records
+            .records
+            .into_iter()
+            .map(|r| Page {
+                id: r.id.to_string(),
+                title: r.title,
+                body: r.body.unwrap_or_default(),
+                files: to_files(r.files),
+            })
+            .collect(),
+        issues: page_records.issues,
+    })
 }
 
 #[worker::send]
 pub async fn get_announcements(
     client: &Client,
     table_ids: &TableIds,
-) -> anyhow::Result<Vec<Announcement>> {
+) -> anyhow::Result<Validated<Vec<Announcement>>> {
     let announcement_records =
-        list_records::<AnnouncementResponse>(client, &table_ids.announcements).await?;
+        list_records::<AnnouncementResponse>(client, "announcements", &table_ids.announcements)
+            .await?;
 
-    Ok(announcement_records
-        .into_iter()
-        .map(|a| Announcement {
-            id: a.id.to_string(),
-            title: a.title,
-            body: a.body.unwrap_or_default(),
-            files: a
-                .files
-                .unwrap_or_default()
-                .into_iter()
-                .map(|f| File {
-                    id: f.id,
-                    name: f.title,
-                    media_type: f.media_type,
-                    signed_url: f.signed_url,
-                })
-                .collect(),
-            created_at: a.creatd_at,
-            updated_at: a.updated_at,
-        })
-        .collect())
+    Ok(Validated {
+        value: announcement_records
+            .records
+            .into_iter()
+            .map(|a| Announcement {
+                id: a.id.to_string(),
+                title: a.title,
+                body: a.body.unwrap_or_default(),
+                files: to_files(a.files),
+                created_at: a.creatd_at,
+                updated_at: a.updated_at,
+            })
+            .collect(),
+        issues: announcement_records.issues,
+    })
 }
 
 #[worker::send]
-pub async fn get_files(client: &Client, table_ids: &TableIds) -> anyhow::Result<Vec<File>> {
+pub async fn get_files(
+    client: &Client,
+    table_ids: &TableIds,
+) -> anyhow::Result<Validated<Vec<File>>> {
     let (about_records_result, page_records_result, announcement_records_result) = futures::join!(
-        list_records::<AboutResponse>(client, &table_ids.about),
-        list_records::<PageResponse>(client, &table_ids.pages),
-        list_records::<AnnouncementResponse>(client, &table_ids.announcements),
+        list_records::<AboutResponse>(client, "about", &table_ids.about),
+        list_records::<PageResponse>(client, "pages", &table_ids.pages),
+        list_records::<AnnouncementResponse>(client, "announcements", &table_ids.announcements),
     );
 
-    let about_files = about_records_result?
+    let about_records = about_records_result?;
+    let page_records = page_records_result?;
+    let announcement_records = announcement_records_result?;
+
+    let about_files = about_records
+        .records
         .into_iter()
         .next_back()
-        .map(|r| r.files.unwrap_or_default())
-        .unwrap_or_default()
-        .into_iter()
-        .map(|f| File {
-            id: f.id,
-            name: f.title,
-            media_type: f.media_type,
-            signed_url: f.signed_url,
-        })
-        .collect::<Vec<_>>();
+        .map(|r| to_files(r.files))
+        .unwrap_or_default();
 
-    let page_files = page_records_result?
+    let page_files = page_records
+        .records
         .into_iter()
-        .flat_map(|r| {
-            r.files.unwrap_or_default().into_iter().map(|f| File {
-                id: f.id,
-                name: f.title,
-                media_type: f.media_type,
-                signed_url: f.signed_url,
-            })
-        })
+        .flat_map(|r| to_files(r.files))
         .collect();
 
-    let announcement_files = announcement_records_result?
+    let announcement_files = announcement_records
+        .records
         .into_iter()
-        .flat_map(|r| {
-            r.files.unwrap_or_default().into_iter().map(|f| File {
-                id: f.id,
-                name: f.title,
-                media_type: f.media_type,
-                signed_url: f.signed_url,
-            })
-        })
+        .flat_map(|r| to_files(r.files))
         .collect();
 
-    Ok([about_files, page_files, announcement_files].concat())
+    Ok(Validated {
+        value: [about_files, page_files, announcement_files].concat(),
+        issues: [
+            about_records.issues,
+            page_records.issues,
+            announcement_records.issues,
+        ]
+        .concat(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start_time: Option<&str>, end_time: Option<&str>) -> EventResponse {
+        serde_json::from_value(serde_json::json!({
+            "ID": 1,
+            "Event Name": "Opening ceremony",
+            "Summary": null,
+            "Description": null,
+            "Start Time": start_time,
+            "End Time": end_time,
+            "Locations": null,
+            "Categories": null,
+            "Hidden": false,
+            "_nc_m2m_tags_events": [],
+            "_nc_m2m_people_events": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn leaves_unscheduled_events_out_without_an_issue() {
+        assert!(check_event_times(&event(None, None)).is_none());
+    }
+
+    #[test]
+    fn accepts_well_formed_times() {
+        assert!(matches!(
+            check_event_times(&event(Some("2026-07-04T10:00:00Z"), None)),
+            Some(Ok(()))
+        ));
+        assert!(matches!(
+            check_event_times(&event(
+                Some("2026-07-04T10:00:00Z"),
+                Some("2026-07-04T11:00:00Z")
+            )),
+            Some(Ok(()))
+        ));
+    }
+
+    #[test]
+    fn reports_malformed_and_backwards_times() {
+        assert!(matches!(
+            check_event_times(&event(Some("next tuesday"), None)),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            check_event_times(&event(Some("2026-07-04T10:00:00Z"), Some("noon"))),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            check_event_times(&event(
+                Some("2026-07-04T11:00:00Z"),
+                Some("2026-07-04T10:00:00Z")
+            )),
+            Some(Err(_))
+        ));
+    }
 }
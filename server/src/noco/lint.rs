@@ -0,0 +1,428 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::noco::Client;
+
+use super::{
+    data::{EventResponse, PeopleResponse, TagResponse, check_event_times, list_records},
+    migrations::TableIds,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    // A row we couldn't read at all.
+    InvalidRow,
+    // An event that isn't hidden, but which we leave out of the schedule anyways.
+    DroppedEvent,
+    LocationDoubleBooked,
+    PersonDoubleBooked,
+    OutsideConDates,
+    MissingCategory,
+    OrphanTag,
+}
+
+// Something in the schedule that's probably a mistake, or that attendees won't see.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub table: String,
+    pub row_ids: Vec<String>,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn new(kind: LintKind, table: &str, row_ids: Vec<String>, message: String) -> Self {
+        Self {
+            kind,
+            table: table.to_string(),
+            row_ids,
+            message,
+        }
+    }
+}
+
+// What we know about the con from the environment config, beyond what's in NocoDB.
+#[derive(Debug, Default)]
+pub struct LintOptions {
+    pub con_start_date: Option<NaiveDate>,
+    pub con_end_date: Option<NaiveDate>,
+    // If this is `None`, we use the UTC offset each time was entered with.
+    pub timezone: Option<Tz>,
+}
+
+// An event that appears in the schedule.
+struct Scheduled<'a> {
+    event: &'a EventResponse,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+}
+
+impl Scheduled<'_> {
+    // Events without an end time still take up their start time.
+    fn overlaps(&self, other: &Scheduled<'_>) -> bool {
+        self.start == other.start || (self.start < other.end && other.start < self.end)
+    }
+
+    fn label(&self) -> String {
+        format!("\"{}\" (ID {})", self.event.name, self.event.id)
+    }
+}
+
+fn local_date(time: DateTime<FixedOffset>, timezone: Option<Tz>) -> NaiveDate {
+    match timezone {
+        Some(timezone) => time.with_timezone(&timezone).date_naive(),
+        None => time.date_naive(),
+    }
+}
+
+// Every pair of events in the same group that overlap in time.
+fn double_bookings<'a, K: Copy + Ord + Hash>(
+    groups: HashMap<K, Vec<&'a Scheduled<'a>>>,
+) -> Vec<(K, &'a Scheduled<'a>, &'a Scheduled<'a>)> {
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort_by_key(|(key, _)| *key);
+
+    let mut pairs = Vec::new();
+
+    for (key, events) in groups {
+        for (i, first) in events.iter().enumerate() {
+            for second in &events[i + 1..] {
+                if first.overlaps(second) {
+                    pairs.push((key, *first, *second));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+fn lint_records(
+    events: &[EventResponse],
+    people: &[PeopleResponse],
+    tags: &[TagResponse],
+    options: &LintOptions,
+) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut scheduled = Vec::new();
+
+    for event in events.iter().filter(|event| !event.hidden) {
+        let id = vec![event.id.to_string()];
+
+        match check_event_times(event) {
+            None => issues.push(LintIssue::new(
+                LintKind::DroppedEvent,
+                "events",
+                id,
+                format!(
+                    "\"{}\" has no start time, so it isn't shown in the schedule.",
+                    event.name
+                ),
+            )),
+            Some(Err(problem)) => issues.push(LintIssue::new(
+                LintKind::DroppedEvent,
+                "events",
+                id,
+                format!(
+                    "\"{}\" isn't shown in the schedule because {problem}.",
+                    event.name
+                ),
+            )),
+            Some(Ok(())) => {
+                // These can't fail, because we just checked them.
+                let parse = |time: &str| DateTime::parse_from_rfc3339(time).ok();
+                let Some(start) = event.start_time.as_deref().and_then(parse) else {
+                    continue;
+                };
+                let end = event.end_time.as_deref().and_then(parse).unwrap_or(start);
+
+                scheduled.push(Scheduled { event, start, end });
+            }
+        }
+    }
+
+    scheduled.sort_by_key(|event| (event.start, event.end));
+
+    let mut by_location = HashMap::<&str, Vec<&Scheduled>>::new();
+    for event in &scheduled {
+        if let Some(location) = &event.event.location {
+            by_location
+                .entry(location.name.as_str())
+                .or_default()
+                .push(event);
+        }
+    }
+
+    for (location, first, second) in double_bookings(by_location) {
+        issues.push(LintIssue::new(
+            LintKind::LocationDoubleBooked,
+            "events",
+            vec![first.event.id.to_string(), second.event.id.to_string()],
+            format!(
+                "{} and {} are both in {location} at the same time.",
+                first.label(),
+                second.label()
+            ),
+        ));
+    }
+
+    let people_names = people
+        .iter()
+        .map(|person| (person.id, person.name.as_str()))
+        .collect::<HashMap<_, _>>();
+
+    let mut by_person = HashMap::<u32, Vec<&Scheduled>>::new();
+    for event in &scheduled {
+        for person in &event.event.people_m2m {
+            if people_names.contains_key(&person.id) {
+                by_person.entry(person.id).or_default().push(event);
+            }
+        }
+    }
+
+    for (person_id, first, second) in double_bookings(by_person) {
+        issues.push(LintIssue::new(
+            LintKind::PersonDoubleBooked,
+            "events",
+            vec![first.event.id.to_string(), second.event.id.to_string()],
+            format!(
+                "{} is in both {} and {} at the same time.",
+                people_names[&person_id],
+                first.label(),
+                second.label()
+            ),
+        ));
+    }
+
+    for event in &scheduled {
+        let starts_early = options
+            .con_start_date
+            .is_some_and(|date| local_date(event.start, options.timezone) < date);
+        let ends_late = options
+            .con_end_date
+            .is_some_and(|date| local_date(event.end, options.timezone) > date);
+
+        if starts_early || ends_late {
+            issues.push(LintIssue::new(
+                LintKind::OutsideConDates,
+                "events",
+                vec![event.event.id.to_string()],
+                format!("{} isn't during the con.", event.label()),
+            ));
+        }
+    }
+
+    for event in scheduled
+        .iter()
+        .filter(|event| event.event.category.is_none())
+    {
+        issues.push(LintIssue::new(
+            LintKind::MissingCategory,
+            "events",
+            vec![event.event.id.to_string()],
+            format!("{} has no category.", event.label()),
+        ));
+    }
+
+    // A tag only used by hidden events isn't an orphan, because the events might be unhidden later.
+    let used_tags = events
+        .iter()
+        .flat_map(|event| &event.tags_m2m)
+        .map(|tag| tag.id)
+        .collect::<HashSet<_>>();
+
+    for tag in tags.iter().filter(|tag| !used_tags.contains(&tag.id)) {
+        issues.push(LintIssue::new(
+            LintKind::OrphanTag,
+            "tags",
+            vec![tag.id.to_string()],
+            format!("The tag \"{}\" isn't used by any events.", tag.name),
+        ));
+    }
+
+    issues
+}
+
+// Check the schedule for events attendees won't see and for likely mistakes. Unlike the cached
+// data, this always reads straight from NocoDB, so organizers can check their fixes right away.
+#[worker::send]
+pub async fn lint_schedule(
+    client: &Client,
+    table_ids: &TableIds,
+    options: &LintOptions,
+) -> anyhow::Result<Vec<LintIssue>> {
+    let (event_records, people_records, tags_records) = futures::try_join!(
+        list_records::<EventResponse>(client, "events", &table_ids.events),
+        list_records::<PeopleResponse>(client, "people", &table_ids.people),
+        list_records::<TagResponse>(client, "tags", &table_ids.tags),
+    )?;
+
+    let mut issues = [
+        event_records.issues,
+        people_records.issues,
+        tags_records.issues,
+    ]
+    .concat()
+    .into_iter()
+    .map(|issue| {
+        LintIssue::new(
+            LintKind::InvalidRow,
+            &issue.table,
+            issue.row_id.into_iter().collect(),
+            issue.problem,
+        )
+    })
+    .collect::<Vec<_>>();
+
+    issues.extend(lint_records(
+        &event_records.records,
+        &people_records.records,
+        &tags_records.records,
+        options,
+    ));
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(
+        id: u32,
+        start_time: Option<&str>,
+        end_time: Option<&str>,
+        location: Option<&str>,
+        people: &[u32],
+    ) -> EventResponse {
+        serde_json::from_value(json!({
+            "ID": id,
+            "Event Name": format!("Event {id}"),
+            "Summary": null,
+            "Description": null,
+            "Start Time": start_time,
+            "End Time": end_time,
+            "Locations": location.map(|name| json!({ "Location": name })),
+            "Categories": { "Category": "Panel" },
+            "Hidden": false,
+            "_nc_m2m_tags_events": [],
+            "_nc_m2m_people_events": people
+                .iter()
+                .map(|id| json!({ "people_id": id }))
+                .collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    fn person(id: u32, name: &str) -> PeopleResponse {
+        serde_json::from_value(json!({ "ID": id, "Name": name })).unwrap()
+    }
+
+    fn kinds(issues: &[LintIssue]) -> Vec<LintKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn reports_dropped_events() {
+        let events = [
+            event(1, None, None, None, &[]),
+            event(
+                2,
+                Some("2026-07-04T11:00:00Z"),
+                Some("2026-07-04T10:00:00Z"),
+                None,
+                &[],
+            ),
+            event(3, Some("2026-07-04T10:00:00Z"), None, None, &[]),
+        ];
+
+        let issues = lint_records(&events, &[], &[], &LintOptions::default());
+
+        assert_eq!(
+            kinds(&issues),
+            [LintKind::DroppedEvent, LintKind::DroppedEvent]
+        );
+        assert_eq!(issues[0].row_ids, ["1"]);
+        assert_eq!(issues[1].row_ids, ["2"]);
+    }
+
+    #[test]
+    fn reports_double_bookings() {
+        let events = [
+            event(
+                1,
+                Some("2026-07-04T10:00:00Z"),
+                Some("2026-07-04T11:00:00Z"),
+                Some("Main Hall"),
+                &[1],
+            ),
+            event(
+                2,
+                Some("2026-07-04T10:30:00Z"),
+                Some("2026-07-04T11:30:00Z"),
+                Some("Main Hall"),
+                &[],
+            ),
+            event(
+                3,
+                Some("2026-07-04T11:00:00Z"),
+                Some("2026-07-04T12:00:00Z"),
+                Some("Room 2"),
+                &[1],
+            ),
+            event(4, Some("2026-07-04T10:45:00Z"), None, Some("Room 3"), &[1]),
+        ];
+
+        let issues = lint_records(&events, &[person(1, "Sam")], &[], &LintOptions::default());
+
+        assert_eq!(
+            kinds(&issues),
+            [LintKind::LocationDoubleBooked, LintKind::PersonDoubleBooked]
+        );
+        assert_eq!(issues[0].row_ids, ["1", "2"]);
+        assert_eq!(issues[1].row_ids, ["1", "4"]);
+    }
+
+    #[test]
+    fn reports_events_outside_the_con_in_its_timezone() {
+        let events = [
+            // This is the evening of July 3rd in New York.
+            event(1, Some("2026-07-04T01:00:00Z"), None, None, &[]),
+            event(2, Some("2026-07-04T14:00:00Z"), None, None, &[]),
+        ];
+
+        let options = LintOptions {
+            con_start_date: NaiveDate::from_ymd_opt(2026, 7, 4),
+            con_end_date: NaiveDate::from_ymd_opt(2026, 7, 5),
+            timezone: Some(chrono_tz::America::New_York),
+        };
+
+        let issues = lint_records(&events, &[], &[], &options);
+
+        assert_eq!(kinds(&issues), [LintKind::OutsideConDates]);
+        assert_eq!(issues[0].row_ids, ["1"]);
+    }
+
+    #[test]
+    fn reports_missing_categories_and_orphan_tags() {
+        let mut uncategorized = event(1, Some("2026-07-04T10:00:00Z"), None, None, &[]);
+        uncategorized.category = None;
+
+        let tag: TagResponse = serde_json::from_value(json!({ "ID": 7, "Tag": "18+" })).unwrap();
+
+        let issues = lint_records(&[uncategorized], &[], &[tag], &LintOptions::default());
+
+        assert_eq!(
+            kinds(&issues),
+            [LintKind::MissingCategory, LintKind::OrphanTag]
+        );
+        assert_eq!(issues[1].row_ids, ["7"]);
+    }
+}
@@ -71,7 +71,8 @@ impl<'a> Migrator<'a> {
         let ctx = MigrationContext {
             env_id,
             api_domain: config::api_domain(),
-            noco_webhook_token: config::noco_webhook_token(),
+            noco_webhook_token: config::noco_webhook_tokens()
+                .map(|tokens| tokens.current().clone()),
         };
 
         let (mut version, base_id) = match state {
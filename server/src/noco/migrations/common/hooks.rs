@@ -0,0 +1,197 @@
+use base64::prelude::*;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde_json::json;
+use worker::{Method, console_log, console_warn};
+
+use crate::noco::Client;
+
+use super::{MigrationContext, TableId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl HookOperation {
+    fn code(self) -> &'static str {
+        match self {
+            HookOperation::Insert => "insert",
+            HookOperation::Update => "update",
+            HookOperation::Delete => "delete",
+        }
+    }
+}
+
+// An after-operation URL hook that posts the event to one of our receiver routes.
+pub struct HookRequest<'a> {
+    // Title we put on the hook record. Used as the idempotency key on re-runs — if a hook with this
+    // title already exists on the table, we leave it alone.
+    pub title: &'a str,
+    pub table: &'a TableId,
+    pub operations: &'a [HookOperation],
+    // The receiver route under `/apps/{env_id}/hooks/`.
+    pub receiver: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct HookSummary {
+    id: String,
+    title: Option<String>,
+    // NocoDB returns this as either a JSON string or an object, depending on the version. We only
+    // need to search it for the bearer token, so we don't care which.
+    #[serde(default)]
+    notification: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListHooksResponse {
+    list: Vec<HookSummary>,
+}
+
+async fn find_hook(client: &Client, hook: &HookRequest<'_>) -> anyhow::Result<Option<HookSummary>> {
+    let response = client
+        .build_request_v2(Method::Get, &format!("/meta/tables/{}/hooks", hook.table))
+        .fetch::<ListHooksResponse>()
+        .await?;
+
+    Ok(response
+        .list
+        .into_iter()
+        .find(|summary| summary.title.as_deref() == Some(hook.title)))
+}
+
+// Returns `None` if hooks can't be installed in this environment yet.
+fn webhook_bearer(ctx: &MigrationContext) -> Option<String> {
+    // The webhook path is per-environment because the server multiplexes every environment behind
+    // one shared origin; the receiver routes by env_id back to the right KV namespace.
+    let Some(env_id) = ctx.env_id.as_ref() else {
+        console_warn!("Skipping NocoDB hooks: this environment has no env_id assigned yet.");
+        return None;
+    };
+    let Some(token) = ctx.noco_webhook_token.as_ref() else {
+        console_warn!(
+            "Skipping NocoDB hooks: NOCO_WEBHOOK_TOKEN is not configured for this worker."
+        );
+        return None;
+    };
+
+    // NocoDB gets a secret that only works for this environment.
+    Some(BASE64_STANDARD.encode(token.derive_for_env(env_id).expose_secret()))
+}
+
+fn notification(
+    ctx: &MigrationContext,
+    hook: &HookRequest<'_>,
+    bearer: &str,
+) -> anyhow::Result<String> {
+    let env_id = ctx
+        .env_id
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("this environment has no env_id assigned yet"))?;
+
+    let url = format!(
+        "https://{}/apps/{env_id}/hooks/{}",
+        ctx.api_domain, hook.receiver,
+    );
+
+    Ok(serde_json::to_string(&json!({
+        "type": "URL",
+        "include_user": false,
+        "payload": {
+            "method": "POST",
+            "path": url,
+            "auth": "",
+            "body": "{{ json event }}",
+            "headers": [
+                {
+                    "enabled": true,
+                    "name": "Authorization",
+                    "value": format!("Bearer {bearer}"),
+                },
+                {
+                    "enabled": true,
+                    "name": "Content-Type",
+                    "value": "application/json",
+                },
+            ],
+            "parameters": [],
+        },
+        "trigger_form": false,
+    }))?)
+}
+
+async fn install_hook(
+    client: &Client,
+    ctx: &MigrationContext,
+    hook: &HookRequest<'_>,
+    bearer: &str,
+) -> anyhow::Result<()> {
+    let body = json!({
+        "title": hook.title,
+        "event": "after",
+        "operation": hook.operations.iter().map(|operation| operation.code()).collect::<Vec<_>>(),
+        "version": "v3",
+        "active": true,
+        "notification": notification(ctx, hook, bearer)?,
+    });
+
+    client
+        .build_request_v2(Method::Post, &format!("/meta/tables/{}/hooks", hook.table))
+        .with_json(&body)?
+        .exec()
+        .await?;
+
+    console_log!("Installed NocoDB hook `{}`", hook.title);
+
+    Ok(())
+}
+
+// When the webhook token is rotated, the worker keeps accepting the previous token until this has
+// replaced it on every hook.
+async fn update_hook_bearer(
+    client: &Client,
+    ctx: &MigrationContext,
+    hook: &HookRequest<'_>,
+    existing: &HookSummary,
+    bearer: &str,
+) -> anyhow::Result<()> {
+    client
+        .build_request_v2(Method::Patch, &format!("/meta/hooks/{}", existing.id))
+        .with_json(&json!({
+            "notification": notification(ctx, hook, bearer)?,
+        }))?
+        .exec()
+        .await?;
+
+    console_log!("Updated the bearer token on NocoDB hook `{}`", hook.title);
+
+    Ok(())
+}
+
+// Idempotent: re-running a migration after its hooks are in place is a no-op, unless the webhook
+// token has been rotated since. This matters because the user can reset migration state and
+// replay.
+pub async fn ensure_hooks(
+    client: &Client,
+    ctx: &MigrationContext,
+    hooks: &[HookRequest<'_>],
+) -> anyhow::Result<()> {
+    let Some(bearer) = webhook_bearer(ctx) else {
+        return Ok(());
+    };
+
+    for hook in hooks {
+        match find_hook(client, hook).await? {
+            Some(existing) if existing.notification.to_string().contains(&bearer) => {
+                console_log!("NocoDB hook `{}` already installed; skipping.", hook.title);
+            }
+            Some(existing) => update_hook_bearer(client, ctx, hook, &existing, &bearer).await?,
+            None => install_hook(client, ctx, hook, &bearer).await?,
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 mod columns;
+mod hooks;
 mod migration;
 mod models;
 mod tables;
@@ -9,6 +10,7 @@ pub use columns::{
     ColumnIds, CreateColumnRequest, EditColumnRequest, create_columns, delete_columns,
     edit_columns, list_columns,
 };
+pub use hooks::{HookOperation, HookRequest, ensure_hooks};
 pub use migration::{Migration, MigrationContext, Version};
 pub use models::{BaseId, ColumnId, TableId, ViewId};
 pub use tables::{TableIds, TableInfo, TableRequest, create_tables, delete_tables, list_tables};
@@ -4,6 +4,9 @@ mod n2;
 mod n3;
 mod n4;
 mod n5;
+mod n6;
+mod n7;
+mod n8;
 
 // Each base schema migration lives in its own module with the name `nX`, where `X` is the
 // incrementing migration number.
@@ -32,6 +35,9 @@ pub async fn run(
         n3::Migration::INDEX => n3::Migration::new(client, ctx).migrate(base_id).await?,
         n4::Migration::INDEX => n4::Migration::new(client, ctx).migrate(base_id).await?,
         n5::Migration::INDEX => n5::Migration::new(client, ctx).migrate(base_id).await?,
+        n6::Migration::INDEX => n6::Migration::new(client, ctx).migrate(base_id).await?,
+        n7::Migration::INDEX => n7::Migration::new(client, ctx).migrate(base_id).await?,
+        n8::Migration::INDEX => n8::Migration::new(client, ctx).migrate(base_id).await?,
         _ => return Ok(Outcome::AlreadyUpToDate),
     }
 
@@ -0,0 +1,46 @@
+use crate::noco::{
+    BaseId, Client, TableIds, Version, list_tables,
+    migrations::{
+        common::{self, HookOperation, HookRequest, MigrationContext},
+        n5,
+    },
+};
+
+pub struct Migration<'a> {
+    client: &'a Client,
+    ctx: &'a MigrationContext,
+}
+
+impl<'a> common::Migration<'a> for Migration<'a> {
+    const INDEX: Version = n5::Migration::INDEX.next();
+
+    fn new(client: &'a Client, ctx: &'a MigrationContext) -> Self {
+        Self { client, ctx }
+    }
+
+    // Edits and deletions refresh the announcements cache, and edits also send a correction
+    // notification that replaces the original.
+    async fn migrate(&self, base_id: BaseId) -> anyhow::Result<()> {
+        let tables = TableIds::try_from(list_tables(self.client, &base_id).await?)?;
+
+        common::ensure_hooks(
+            self.client,
+            self.ctx,
+            &[
+                HookRequest {
+                    title: "FanJam announcement edits",
+                    table: &tables.announcements,
+                    operations: &[HookOperation::Update],
+                    receiver: "announcement-updated",
+                },
+                HookRequest {
+                    title: "FanJam announcement deletions",
+                    table: &tables.announcements,
+                    operations: &[HookOperation::Delete],
+                    receiver: "announcement-deleted",
+                },
+            ],
+        )
+        .await
+    }
+}
@@ -0,0 +1,56 @@
+use crate::noco::{
+    BaseId, Client, TableIds, Version, list_tables,
+    migrations::{
+        common::{self, HookOperation, HookRequest, MigrationContext},
+        n6,
+    },
+};
+
+const HOOK_TITLE: &str = "FanJam cache refresh";
+
+const ALL_OPERATIONS: &[HookOperation] = &[
+    HookOperation::Insert,
+    HookOperation::Update,
+    HookOperation::Delete,
+];
+
+pub struct Migration<'a> {
+    client: &'a Client,
+    ctx: &'a MigrationContext,
+}
+
+impl<'a> common::Migration<'a> for Migration<'a> {
+    const INDEX: Version = n6::Migration::INDEX.next();
+
+    fn new(client: &'a Client, ctx: &'a MigrationContext) -> Self {
+        Self { client, ctx }
+    }
+
+    // Refresh the cached data built from each table as soon as it changes, rather than waiting for
+    // the edge cache to expire and a background refresh. Announcements already have their own
+    // hooks, and the files table is no longer read.
+    async fn migrate(&self, base_id: BaseId) -> anyhow::Result<()> {
+        let tables = TableIds::try_from(list_tables(self.client, &base_id).await?)?;
+
+        let hook = |table, receiver| HookRequest {
+            title: HOOK_TITLE,
+            table,
+            operations: ALL_OPERATIONS,
+            receiver,
+        };
+
+        common::ensure_hooks(
+            self.client,
+            self.ctx,
+            &[
+                hook(&tables.events, "table-changed/events"),
+                hook(&tables.people, "table-changed/people"),
+                hook(&tables.tags, "table-changed/tags"),
+                hook(&tables.about, "table-changed/about"),
+                hook(&tables.links, "table-changed/links"),
+                hook(&tables.pages, "table-changed/pages"),
+            ],
+        )
+        .await
+    }
+}
@@ -0,0 +1,29 @@
+use crate::noco::{
+    BaseId, Client, TableIds, Version, list_tables,
+    migrations::{
+        common::{self, MigrationContext},
+        n5, n7,
+    },
+};
+
+pub struct Migration<'a> {
+    client: &'a Client,
+    ctx: &'a MigrationContext,
+}
+
+impl<'a> common::Migration<'a> for Migration<'a> {
+    const INDEX: Version = n7::Migration::INDEX.next();
+
+    fn new(client: &'a Client, ctx: &'a MigrationContext) -> Self {
+        Self { client, ctx }
+    }
+
+    // Webhooks now authenticate with a secret for each environment rather than the worker-wide
+    // token, but the push notifications hook from n5 was installed with the worker-wide token.
+    // Re-applying it swaps in the new secret. The hooks from later migrations already have it.
+    async fn migrate(&self, base_id: BaseId) -> anyhow::Result<()> {
+        let tables = TableIds::try_from(list_tables(self.client, &base_id).await?)?;
+
+        common::ensure_hooks(self.client, self.ctx, &[n5::hook(&tables)]).await
+    }
+}
@@ -1,14 +1,16 @@
 mod base;
 mod client;
 mod data;
+mod lint;
 mod migrate;
 mod migrations;
 
 pub use base::{check_base_exists, create_base, delete_base};
 pub use client::{ApiToken, Client};
 pub use data::{
-    Announcement, Event, File, Info, Page, get_announcements, get_events, get_files, get_info,
-    get_pages,
+    Announcement, Event, File, Info, Page, Validated, ValidationReport, get_announcements,
+    get_events, get_files, get_info, get_pages,
 };
+pub use lint::{LintIssue, LintOptions, lint_schedule};
 pub use migrate::{ExistingMigrationState, MigrationState, Migrator};
 pub use migrations::{BaseId, TableIds, TableInfo, Version, list_tables};
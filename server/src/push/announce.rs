@@ -1,8 +1,20 @@
+use chrono::Utc;
 use worker::{console_warn, kv::KvStore};
 
 use crate::env::EnvName;
 use crate::kv;
-use crate::push::client::{Client, DeliveryOutcome};
+use crate::push::client::{Client, DeliveryOutcome, Subscription};
+
+// Keeping subscription metadata up to date is best-effort, so a KV error here shouldn't stop the
+// announcement from reaching everyone else.
+async fn evict(kv: &KvStore, env_name: &EnvName, subscription: &Subscription) {
+    if let Err(e) = kv::delete_subscription(kv, env_name, &subscription.id()).await {
+        console_warn!(
+            "Failed evicting subscription for endpoint {}: {e}",
+            subscription.endpoint,
+        );
+    }
+}
 
 pub async fn push_notifications(
     kv: &KvStore,
@@ -10,12 +22,34 @@ pub async fn push_notifications(
     client: &Client,
     payload: &[u8],
 ) -> anyhow::Result<()> {
-    let subscriptions = kv::list_subscriptions(kv, env_name).await?;
-    for subscription in subscriptions {
-        match client.send(&subscription, payload).await {
-            Ok(DeliveryOutcome::Delivered) => {}
+    let records = kv::list_subscriptions(kv, env_name).await?;
+    for mut record in records {
+        let subscription = &record.subscription;
+
+        // Subscriptions stored before we validated them on registration may
+        // point anywhere, so check them again before sending anything.
+        if let Err(e) = subscription.validate() {
+            console_warn!(
+                "Evicting invalid subscription for endpoint {}: {e}",
+                subscription.endpoint,
+            );
+            evict(kv, env_name, subscription).await;
+            continue;
+        }
+
+        let delivered = match client.send(&record, payload).await {
+            Ok(DeliveryOutcome::Delivered) => true,
             Ok(DeliveryOutcome::SubscriptionGone) => {
-                kv::delete_subscription(kv, env_name, &subscription.id()).await?;
+                evict(kv, env_name, subscription).await;
+                continue;
+            }
+            Ok(DeliveryOutcome::KeyRetired) => {
+                console_warn!(
+                    "Evicting subscription for endpoint {} made against a retired VAPID key",
+                    subscription.endpoint,
+                );
+                evict(kv, env_name, subscription).await;
+                continue;
             }
             Ok(DeliveryOutcome::OtherStatus(code)) => {
                 console_warn!(
@@ -23,13 +57,24 @@ pub async fn push_notifications(
                     code,
                     subscription.endpoint,
                 );
+                false
             }
             Err(e) => {
                 console_warn!(
                     "Push send failed for endpoint {}: {e}",
                     subscription.endpoint,
                 );
+                false
             }
+        };
+
+        if record.record_delivery(delivered, Utc::now())
+            && let Err(e) = kv::put_subscription(kv, env_name, &record).await
+        {
+            console_warn!(
+                "Failed recording delivery for endpoint {}: {e}",
+                record.subscription.endpoint,
+            );
         }
     }
     Ok(())
@@ -6,11 +6,12 @@
 use axum::http::StatusCode;
 use base64::prelude::*;
 use serde::Deserialize;
-use worker::Method;
+use worker::{Method, Url};
 
 use crate::http;
 use crate::push::encrypt::{PUBLIC_KEY_LEN, Sender};
-use crate::push::vapid::{self, VapidKey};
+use crate::push::record::SubscriptionRecord;
+use crate::push::vapid::{self, VapidKeys};
 
 /// Default time-to-live for a push message at the push service, in seconds.
 /// 24 hours is what every reference client uses; if a subscriber is offline
@@ -20,6 +21,23 @@ const DEFAULT_TTL_SECS: u32 = 24 * 60 * 60;
 /// Length of the per-subscription `auth` secret. RFC 8291 §3.2.
 const AUTH_LEN: usize = 16;
 
+/// Hosts of the push services we'll deliver to, matched exactly or as a
+/// parent domain. Subscriptions for any other endpoint are refused at
+/// registration so a malicious client can't turn announcements into requests
+/// against arbitrary URLs.
+const ALLOWED_PUSH_HOSTS: &[&str] = &[
+    // Chrome and other Chromium-based browsers.
+    "fcm.googleapis.com",
+    // Older Chrome subscriptions from before FCM.
+    "android.googleapis.com",
+    // Firefox.
+    "push.services.mozilla.com",
+    // Safari.
+    "push.apple.com",
+    // Edge.
+    "notify.windows.com",
+];
+
 /// JSON shape produced by `PushSubscription.toJSON()` in the browser; this
 /// is what the client POSTs to `/apps/{env}/subscription` and what we store
 /// in KV. Field names match the wire format exactly.
@@ -35,6 +53,46 @@ impl Subscription {
     pub fn id(&self) -> String {
         endpoint_id(&self.endpoint)
     }
+
+    /// Check that this is a subscription we could actually deliver to: an
+    /// HTTPS endpoint on a known push service, with keys that
+    /// [`Client::send`] can encrypt for.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let url = Url::parse(&self.endpoint)
+            .map_err(|e| anyhow::anyhow!("subscription endpoint is not a valid URL: {e}"))?;
+
+        if url.scheme() != "https" {
+            anyhow::bail!("subscription endpoint must use HTTPS");
+        }
+
+        // `port` is `None` when the URL uses the default port for its scheme.
+        if url.port().is_some() {
+            anyhow::bail!("subscription endpoint must use the default port");
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow::anyhow!("subscription endpoint has no host"))?;
+
+        let is_allowed = ALLOWED_PUSH_HOSTS.iter().any(|allowed| {
+            host == *allowed
+                || host
+                    .strip_suffix(allowed)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        });
+
+        if !is_allowed {
+            anyhow::bail!("subscription endpoint {host} is not a known push service");
+        }
+
+        let p256dh = decode_b64url_fixed::<PUBLIC_KEY_LEN>(&self.keys.p256dh, "p256dh")?;
+        p256::PublicKey::from_sec1_bytes(&p256dh)
+            .map_err(|_| anyhow::anyhow!("subscription p256dh is not a valid P-256 point"))?;
+
+        decode_b64url_fixed::<AUTH_LEN>(&self.keys.auth, "auth")?;
+
+        Ok(())
+    }
 }
 
 /// Stable identifier derived from a subscription endpoint URL alone. Used
@@ -72,25 +130,40 @@ pub enum DeliveryOutcome {
     /// limited, 5xx). Surfaced but not retried; we trust the push service's
     /// own ttl/retry semantics for the next announcement.
     OtherStatus(StatusCode),
+    /// Subscription was created against a VAPID key we no longer hold, so
+    /// the push service would reject anything we signed. Caller should
+    /// delete it; the browser will re-subscribe against the current key.
+    KeyRetired,
 }
 
 /// Reusable per-environment push sender.
 pub struct Client {
-    vapid: VapidKey,
+    vapid: VapidKeys,
 }
 
 impl Client {
-    pub fn new(vapid: VapidKey) -> Self {
+    pub fn new(vapid: VapidKeys) -> Self {
         Self { vapid }
     }
 
-    /// Encrypt `payload` for `subscription` and POST it to the push service.
-    /// Uses a fresh ephemeral keypair + salt per call (required by RFC 8291).
+    /// Encrypt `payload` for the subscription in `record` and POST it to the
+    /// push service, signed with the VAPID key the subscription was created
+    /// against. Uses a fresh ephemeral keypair + salt per call (required by
+    /// RFC 8291).
     pub async fn send(
         &self,
-        subscription: &Subscription,
+        record: &SubscriptionRecord,
         payload: &[u8],
     ) -> anyhow::Result<DeliveryOutcome> {
+        let subscription = &record.subscription;
+
+        let Some(vapid_key) = self
+            .vapid
+            .for_subscription(record.vapid_public_key.as_deref())
+        else {
+            return Ok(DeliveryOutcome::KeyRetired);
+        };
+
         let p256dh = decode_b64url_fixed::<PUBLIC_KEY_LEN>(&subscription.keys.p256dh, "p256dh")?;
         let auth = decode_b64url_fixed::<AUTH_LEN>(&subscription.keys.auth, "auth")?;
 
@@ -99,7 +172,7 @@ impl Client {
 
         let audience = vapid::audience_from_endpoint(&subscription.endpoint)?;
         let issued_at = chrono::Utc::now().timestamp();
-        let auth_header = vapid::build_authorization_header(&self.vapid, &audience, issued_at)?;
+        let auth_header = vapid::build_authorization_header(vapid_key, &audience, issued_at)?;
 
         let status = http::RequestBuilder::new(Method::Post, &subscription.endpoint)
             .with_header("Authorization", &auth_header)
@@ -120,13 +193,12 @@ impl Client {
         })
     }
 
-    /// The VAPID public key the client also has in its `VITE_VAPID_PUBLIC_KEY`
-    /// build var. Exposed for the public config endpoint so the client can
-    /// double-check the key it was built with against what the server is
-    /// signing with (mismatch is a deploy bug, not a runtime condition).
-    #[allow(dead_code)] // wired up in slice 2
+    /// The current VAPID public key, which every new subscription should be
+    /// created against. Exposed via the public config endpoint so clients
+    /// subscribed with an older key (or built with a stale
+    /// `VITE_VAPID_PUBLIC_KEY`) can notice and re-subscribe.
     pub fn vapid_public_key_b64(&self) -> &str {
-        self.vapid.public_key_b64()
+        self.vapid.current().public_key_b64()
     }
 }
 
@@ -144,8 +216,61 @@ fn decode_b64url_fixed<const N: usize>(s: &str, name: &str) -> anyhow::Result<[u
 
 #[cfg(test)]
 mod tests {
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+
     use super::*;
 
+    fn subscription(endpoint: &str) -> Subscription {
+        let public_key = p256::SecretKey::from_slice(&[1u8; 32])
+            .unwrap()
+            .public_key()
+            .to_encoded_point(false);
+
+        Subscription {
+            endpoint: endpoint.to_string(),
+            keys: SubscriptionKeys {
+                p256dh: BASE64_URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+                auth: BASE64_URL_SAFE_NO_PAD.encode([2u8; AUTH_LEN]),
+            },
+        }
+    }
+
+    #[test]
+    fn accepts_subscriptions_for_known_push_services() {
+        for endpoint in [
+            "https://fcm.googleapis.com/fcm/send/abc",
+            "https://updates.push.services.mozilla.com/wpush/v2/abc",
+            "https://web.push.apple.com/abc",
+            "https://wns2-par02p.notify.windows.com/w/?token=abc",
+        ] {
+            assert!(subscription(endpoint).validate().is_ok(), "{endpoint}");
+        }
+    }
+
+    #[test]
+    fn rejects_endpoints_that_are_not_known_push_services() {
+        for endpoint in [
+            "http://fcm.googleapis.com/fcm/send/abc",
+            "https://fcm.googleapis.com:8443/fcm/send/abc",
+            "https://evilfcm.googleapis.com.example.com/abc",
+            "https://notfcm.googleapis.com.evil/abc",
+            "https://example.com/abc",
+            "https://169.254.169.254/latest/meta-data",
+        ] {
+            assert!(subscription(endpoint).validate().is_err(), "{endpoint}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_p256dh_that_is_not_a_curve_point() {
+        let mut subscription = subscription("https://fcm.googleapis.com/fcm/send/abc");
+        let mut not_a_point = [0u8; PUBLIC_KEY_LEN];
+        not_a_point[0] = 0x04;
+        subscription.keys.p256dh = BASE64_URL_SAFE_NO_PAD.encode(not_a_point);
+
+        assert!(subscription.validate().is_err());
+    }
+
     #[test]
     fn rejects_subscription_key_with_wrong_length() {
         // 43 base64url chars decode to 32 bytes, but a p256dh must be 65.
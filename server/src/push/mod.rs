@@ -8,9 +8,13 @@ mod announce;
 mod client;
 mod encrypt;
 mod notification;
+mod prune;
+mod record;
 mod vapid;
 
 pub use announce::push_notifications;
 pub use client::{Client, Subscription, endpoint_id};
-pub use notification::{Payload, markdown_to_plain_text};
-pub use vapid::VapidKey;
+pub use notification::{Action, Payload, announcement_tag, markdown_to_plain_text};
+pub use prune::prune_subscriptions;
+pub use record::{SubscriptionMetadata, SubscriptionRecord};
+pub use vapid::{VapidKey, VapidKeys};
@@ -11,13 +11,46 @@ use serde::Serialize;
 const MAX_BODY_CHARS: usize = 150;
 
 /// Wire shape sent to the service worker inside the encrypted push body.
-/// Fields match the keys the SW reads in its `push` handler.
+/// Fields match the keys the SW reads in its `push` handler, which passes
+/// most of them straight through to `showNotification`.
 #[derive(Debug, Serialize)]
-pub struct Payload<'a> {
-    pub title: &'a str,
+pub struct Payload {
+    pub title: String,
     pub body: String,
+    /// Relative path to open when the notification itself is clicked.
     pub url: String,
     pub icon: Option<String>,
+    /// Notifications with the same tag replace each other on the device
+    /// instead of stacking up, so an edited announcement supersedes the
+    /// notification for the original.
+    pub tag: Option<String>,
+    /// Whether to alert again when this replaces an earlier notification
+    /// with the same tag, rather than updating it silently.
+    pub renotify: bool,
+    /// Large image shown in the body of the notification, on platforms
+    /// that support it.
+    pub image: Option<String>,
+    /// Small monochrome icon for the status bar. The SW falls back to the
+    /// app icon.
+    pub badge: Option<String>,
+    pub actions: Vec<Action>,
+    /// When the thing we're notifying about happened, in milliseconds since
+    /// the Unix epoch, which may be earlier than when the push arrives.
+    pub timestamp: Option<i64>,
+}
+
+/// A button on the notification. The SW opens `url` when it's clicked.
+#[derive(Debug, Serialize)]
+pub struct Action {
+    pub action: &'static str,
+    pub title: &'static str,
+    pub url: String,
+}
+
+/// The notification tag for an announcement, so every notification about
+/// the same announcement replaces the last.
+pub fn announcement_tag(announcement_id: &str) -> String {
+    format!("announcement-{announcement_id}")
 }
 
 /// Flatten a Markdown string to a single line of plain text, then truncate
@@ -111,6 +144,32 @@ mod tests {
         assert!(!before_ellipsis.ends_with('a'));
     }
 
+    #[test]
+    fn payload_serializes_the_keys_the_service_worker_reads() {
+        let payload = Payload {
+            title: "Room change".into(),
+            body: "Panel moved.".into(),
+            url: "/announcements/7".into(),
+            icon: None,
+            tag: Some(announcement_tag("7")),
+            renotify: true,
+            image: None,
+            badge: None,
+            actions: vec![Action {
+                action: "view",
+                title: "View",
+                url: "/announcements/7".into(),
+            }],
+            timestamp: Some(1_700_000_000_000),
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["tag"], "announcement-7");
+        assert_eq!(json["renotify"], true);
+        assert_eq!(json["actions"][0]["action"], "view");
+        assert_eq!(json["actions"][0]["url"], "/announcements/7");
+        assert_eq!(json["timestamp"], 1_700_000_000_000_i64);
+    }
+
     #[test]
     fn short_input_is_not_truncated() {
         let input = "Just a quick announcement.";
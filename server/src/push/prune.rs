@@ -0,0 +1,47 @@
+//! Scheduled eviction of subscriptions that are no longer worth sending to,
+//! so dead subscriptions from last year's con don't cost KV reads and push
+//! requests on every announcement.
+
+use chrono::{DateTime, Utc};
+use worker::{console_log, kv::KvStore};
+
+use crate::env::EnvName;
+use crate::kv;
+
+/// When the last event of the con ends, from the cached events. If nothing
+/// is cached we don't know, and only prune subscriptions that keep failing.
+async fn event_ended_at(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let events = kv::get_cached_events(kv, env_name)
+        .await?
+        .map(|entry| entry.value())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(events
+        .iter()
+        .filter_map(|event| {
+            DateTime::parse_from_rfc3339(event.end_time.as_deref().unwrap_or(&event.start_time))
+                .ok()
+        })
+        .map(|time| time.to_utc())
+        .max())
+}
+
+pub async fn prune_subscriptions(kv: &KvStore, env_name: &EnvName) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let event_ended_at = event_ended_at(kv, env_name).await?;
+    let mut pruned = 0;
+
+    for (subscription_id, metadata) in kv::list_subscription_metadata(kv, env_name).await? {
+        if metadata.should_prune(now, event_ended_at) {
+            kv::delete_subscription(kv, env_name, &subscription_id).await?;
+            pruned += 1;
+        }
+    }
+
+    if pruned > 0 {
+        console_log!("Pruned {pruned} push subscriptions for {env_name}");
+    }
+
+    Ok(())
+}
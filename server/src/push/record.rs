@@ -0,0 +1,265 @@
+//! What we store in KV for each subscription, along with enough bookkeeping
+//! to prune subscriptions that are no longer worth sending to.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::push::client::Subscription;
+
+/// Evict a subscription after this many deliveries in a row have failed
+/// without the push service telling us it's gone.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// How long after the last event of a con ends we keep subscriptions that
+/// haven't been confirmed since. Organizers sometimes post a thank-you or a
+/// lost-and-found announcement in the days after.
+const PRUNE_AFTER_EVENT_ENDED: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// We only write a successful delivery back to KV if the last one we recorded
+/// is older than this. Writing on every delivery would cost a KV write per
+/// subscriber per announcement.
+const DELIVERY_RECORD_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cap on the stored user agent, which is only for debugging.
+const MAX_USER_AGENT_CHARS: usize = 256;
+
+/// Records written before we tracked metadata deserialize with it empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionRecord {
+    #[serde(flatten)]
+    pub subscription: Subscription,
+    #[serde(flatten)]
+    pub metadata: SubscriptionMetadata,
+    /// The VAPID public key the subscription was created against, which is
+    /// the key we have to sign with when sending to it.
+    #[serde(default)]
+    pub vapid_public_key: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+/// The part of the record we also store as KV key metadata, so pruning can
+/// decide what to evict from a `list` alone without reading every value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubscriptionMetadata {
+    /// When the browser first registered this subscription with us.
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// When the browser last registered this subscription with us. Browsers
+    /// re-register when the app is opened, so this shows the subscriber is
+    /// still around.
+    #[serde(default)]
+    pub last_seen_at: Option<String>,
+    #[serde(default)]
+    pub last_delivered_at: Option<String>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+fn truncate_user_agent(user_agent: Option<String>) -> Option<String> {
+    user_agent.map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_CHARS).collect())
+}
+
+fn parse_time(time: Option<&str>) -> Option<DateTime<Utc>> {
+    time.and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.to_utc())
+}
+
+impl SubscriptionRecord {
+    pub fn new(
+        subscription: Subscription,
+        vapid_public_key: Option<String>,
+        user_agent: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            subscription,
+            metadata: SubscriptionMetadata {
+                created_at: Some(now.to_rfc3339()),
+                last_seen_at: Some(now.to_rfc3339()),
+                last_delivered_at: None,
+                consecutive_failures: 0,
+            },
+            vapid_public_key,
+            user_agent: truncate_user_agent(user_agent),
+        }
+    }
+
+    /// Update the record when the browser registers the subscription again.
+    /// This keeps when it was created and how deliveries have gone.
+    pub fn seen_again(
+        &mut self,
+        subscription: Subscription,
+        vapid_public_key: Option<String>,
+        user_agent: Option<String>,
+        now: DateTime<Utc>,
+    ) {
+        self.subscription = subscription;
+        self.metadata.last_seen_at = Some(now.to_rfc3339());
+
+        if vapid_public_key.is_some() {
+            self.vapid_public_key = vapid_public_key;
+        }
+
+        if user_agent.is_some() {
+            self.user_agent = truncate_user_agent(user_agent);
+        }
+    }
+
+    /// Record a delivery attempt. This returns whether the record changed
+    /// enough that it's worth writing back to KV.
+    pub fn record_delivery(&mut self, delivered: bool, now: DateTime<Utc>) -> bool {
+        if !delivered {
+            self.metadata.consecutive_failures += 1;
+            return true;
+        }
+
+        let recently_recorded = parse_time(self.metadata.last_delivered_at.as_deref())
+            .and_then(|last_delivered_at| (now - last_delivered_at).to_std().ok())
+            .is_some_and(|elapsed| elapsed < DELIVERY_RECORD_INTERVAL);
+
+        if self.metadata.consecutive_failures == 0 && recently_recorded {
+            return false;
+        }
+
+        self.metadata.consecutive_failures = 0;
+        self.metadata.last_delivered_at = Some(now.to_rfc3339());
+        true
+    }
+}
+
+impl SubscriptionMetadata {
+    /// The last time we knew this subscription was good.
+    fn last_confirmed_at(&self) -> Option<DateTime<Utc>> {
+        parse_time(self.last_delivered_at.as_deref())
+            .into_iter()
+            .chain(parse_time(self.last_seen_at.as_deref()))
+            .chain(parse_time(self.created_at.as_deref()))
+            .max()
+    }
+
+    /// Whether to evict this subscription, given when the con's last event
+    /// ended, if we know.
+    pub fn should_prune(&self, now: DateTime<Utc>, event_ended_at: Option<DateTime<Utc>>) -> bool {
+        if self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            return true;
+        }
+
+        let Some(event_ended_at) = event_ended_at else {
+            return false;
+        };
+
+        let con_is_long_over = (now - event_ended_at)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed > PRUNE_AFTER_EVENT_ENDED);
+
+        let confirmed_since = self
+            .last_confirmed_at()
+            .is_some_and(|last_confirmed_at| last_confirmed_at > event_ended_at);
+
+        con_is_long_over && !confirmed_since
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn metadata(created_at: DateTime<Utc>, consecutive_failures: u32) -> SubscriptionMetadata {
+        SubscriptionMetadata {
+            created_at: Some(created_at.to_rfc3339()),
+            last_seen_at: None,
+            last_delivered_at: None,
+            consecutive_failures,
+        }
+    }
+
+    #[test]
+    fn prunes_subscriptions_that_keep_failing() {
+        let now = Utc::now();
+
+        assert!(!metadata(now, MAX_CONSECUTIVE_FAILURES - 1).should_prune(now, None));
+        assert!(metadata(now, MAX_CONSECUTIVE_FAILURES).should_prune(now, None));
+    }
+
+    #[test]
+    fn prunes_subscriptions_not_confirmed_since_a_con_that_is_long_over() {
+        let now = Utc::now();
+        let ended_recently = now - TimeDelta::days(1);
+        let ended_long_ago = now - TimeDelta::days(60);
+
+        let before = metadata(ended_long_ago - TimeDelta::days(1), 0);
+        let after = metadata(ended_long_ago + TimeDelta::days(1), 0);
+
+        assert!(before.should_prune(now, Some(ended_long_ago)));
+        assert!(!after.should_prune(now, Some(ended_long_ago)));
+        assert!(!before.should_prune(now, Some(ended_recently)));
+        assert!(SubscriptionMetadata::default().should_prune(now, Some(ended_long_ago)));
+    }
+
+    #[test]
+    fn only_records_successful_deliveries_occasionally() {
+        let now = Utc::now();
+        let mut record = SubscriptionRecord {
+            subscription: serde_json::from_value(serde_json::json!({
+                "endpoint": "https://fcm.googleapis.com/fcm/send/abc",
+                "keys": { "p256dh": "", "auth": "" },
+            }))
+            .unwrap(),
+            metadata: metadata(now, 0),
+            vapid_public_key: None,
+            user_agent: None,
+        };
+
+        assert!(record.record_delivery(true, now));
+        assert!(!record.record_delivery(true, now + TimeDelta::hours(1)));
+        assert!(record.record_delivery(false, now + TimeDelta::hours(2)));
+        assert_eq!(record.metadata.consecutive_failures, 1);
+        assert!(record.record_delivery(true, now + TimeDelta::hours(3)));
+        assert_eq!(record.metadata.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn keeps_history_when_seen_again() {
+        let now = Utc::now();
+        let subscription: Subscription = serde_json::from_value(serde_json::json!({
+            "endpoint": "https://fcm.googleapis.com/fcm/send/abc",
+            "keys": { "p256dh": "", "auth": "" },
+        }))
+        .unwrap();
+
+        let mut record = SubscriptionRecord::new(
+            subscription.clone(),
+            Some("key".to_string()),
+            Some("Firefox".to_string()),
+            now,
+        );
+        record.record_delivery(false, now + TimeDelta::hours(1));
+
+        let later = now + TimeDelta::days(1);
+        record.seen_again(subscription, None, Some("Chrome".to_string()), later);
+
+        assert_eq!(record.metadata.created_at, Some(now.to_rfc3339()));
+        assert_eq!(record.metadata.last_seen_at, Some(later.to_rfc3339()));
+        assert_eq!(record.metadata.consecutive_failures, 1);
+        assert_eq!(record.vapid_public_key.as_deref(), Some("key"));
+        assert_eq!(record.user_agent.as_deref(), Some("Chrome"));
+    }
+
+    #[test]
+    fn reads_records_stored_before_metadata() {
+        let record: SubscriptionRecord = serde_json::from_value(serde_json::json!({
+            "endpoint": "https://fcm.googleapis.com/fcm/send/abc",
+            "keys": { "p256dh": "", "auth": "" },
+        }))
+        .unwrap();
+
+        assert!(record.metadata.created_at.is_none());
+        assert!(record.vapid_public_key.is_none());
+        assert_eq!(record.metadata.consecutive_failures, 0);
+    }
+}
@@ -66,6 +66,47 @@ impl VapidKey {
     }
 }
 
+/// The VAPID identities we can sign with. Browsers bind each subscription to
+/// the `applicationServerKey` it was created with, so rotating the key means
+/// keeping the old one around until the subscriptions made against it have
+/// re-subscribed or been pruned. New subscriptions are always made against
+/// `current`.
+#[derive(Clone)]
+pub struct VapidKeys {
+    current: VapidKey,
+    previous: Option<VapidKey>,
+}
+
+impl VapidKeys {
+    pub fn new(current: VapidKey, previous: Option<VapidKey>) -> Self {
+        Self { current, previous }
+    }
+
+    pub fn current(&self) -> &VapidKey {
+        &self.current
+    }
+
+    /// The key whose public half is `public_key_b64`, if it's one we still
+    /// hold.
+    pub fn find(&self, public_key_b64: &str) -> Option<&VapidKey> {
+        let public_key_b64 = public_key_b64.trim().trim_end_matches('=');
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.public_key_b64 == public_key_b64)
+    }
+
+    /// The key to sign with for a subscription created against
+    /// `public_key_b64`. Subscriptions stored before we recorded the key were
+    /// all made against whatever key was current before the first rotation,
+    /// which is `previous` if we've rotated since and `current` otherwise.
+    pub fn for_subscription(&self, public_key_b64: Option<&str>) -> Option<&VapidKey> {
+        match public_key_b64 {
+            Some(public_key_b64) => self.find(public_key_b64),
+            None => Some(self.previous.as_ref().unwrap_or(&self.current)),
+        }
+    }
+}
+
 /// Serializable JWT claim set per RFC 8292 §2.
 #[derive(Serialize)]
 struct Claims<'a> {
@@ -191,6 +232,51 @@ mod tests {
             .expect("signature must verify under VAPID public key");
     }
 
+    #[test]
+    fn picks_the_key_a_subscription_was_created_against() {
+        let current =
+            VapidKey::from_base64url(TEST_PRIVATE_B64, "mailto:test@example.com").unwrap();
+        let previous = VapidKey::from_base64url(
+            &BASE64_URL_SAFE_NO_PAD.encode([7u8; 32]),
+            "mailto:test@example.com",
+        )
+        .unwrap();
+        let retired = VapidKey::from_base64url(
+            &BASE64_URL_SAFE_NO_PAD.encode([8u8; 32]),
+            "mailto:test@example.com",
+        )
+        .unwrap();
+
+        let keys = VapidKeys::new(current.clone(), Some(previous.clone()));
+        let signing_key = |public_key: Option<&str>| {
+            keys.for_subscription(public_key)
+                .map(|key| key.public_key_b64().to_string())
+        };
+
+        assert_eq!(
+            signing_key(Some(current.public_key_b64())).as_deref(),
+            Some(current.public_key_b64()),
+        );
+        assert_eq!(
+            signing_key(Some(previous.public_key_b64())).as_deref(),
+            Some(previous.public_key_b64()),
+        );
+        assert_eq!(signing_key(Some(retired.public_key_b64())), None);
+        // Subscriptions from before we recorded the key predate the rotation.
+        assert_eq!(
+            signing_key(None).as_deref(),
+            Some(previous.public_key_b64()),
+        );
+
+        let unrotated = VapidKeys::new(current.clone(), None);
+        assert_eq!(
+            unrotated
+                .for_subscription(None)
+                .map(|key| key.public_key_b64()),
+            Some(current.public_key_b64()),
+        );
+    }
+
     #[test]
     fn header_is_deterministic_for_fixed_inputs() {
         // RFC 6979 deterministic signing means two builds with the same
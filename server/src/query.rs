@@ -0,0 +1,335 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, de::IntoDeserializer};
+
+use crate::{api::EventField, noco};
+
+// The query params as they're sent, before we check them.
+#[derive(Debug, Default, Deserialize)]
+pub struct EventQuery {
+    // A date, like `2026-07-04`, in the con's timezone.
+    day: Option<String>,
+    location: Option<String>,
+    category: Option<String>,
+    tag: Option<String>,
+    person: Option<String>,
+    // RFC 3339 times. Events that overlap this range match.
+    from: Option<String>,
+    to: Option<String>,
+    // A comma-separated list of event fields to return.
+    fields: Option<String>,
+}
+
+// Which events to return from `/apps/{env_id}/events`, and which of their fields. This runs over
+// the cached events, so it doesn't cost anything extra upstream. Params that are present but empty
+// are treated as absent.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    day: Option<NaiveDate>,
+    location: Option<String>,
+    category: Option<String>,
+    tag: Option<String>,
+    person: Option<String>,
+    from: Option<DateTime<FixedOffset>>,
+    to: Option<DateTime<FixedOffset>>,
+    // If this is `None`, every field is returned.
+    fields: Option<Arc<[EventField]>>,
+}
+
+fn parse_time(param: &str, time: &str) -> anyhow::Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(time)
+        .map_err(|_| anyhow::anyhow!("`{param}` must be a time, like `2026-07-04T10:00:00Z`."))
+}
+
+// We always return the ID, so clients can tell which event is which.
+fn parse_fields(fields: &str) -> anyhow::Result<Arc<[EventField]>> {
+    let mut parsed = vec![EventField::Id];
+
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            field.into_deserializer();
+        parsed.push(
+            EventField::deserialize(deserializer)
+                .map_err(|_| anyhow::anyhow!("`{field}` is not an event field."))?,
+        );
+    }
+
+    parsed.sort();
+    parsed.dedup();
+
+    Ok(parsed.into())
+}
+
+fn non_empty(param: Option<String>) -> Option<String> {
+    param.filter(|value| !value.is_empty())
+}
+
+impl EventFilter {
+    pub fn parse(query: EventQuery) -> anyhow::Result<Self> {
+        let query = EventQuery {
+            day: non_empty(query.day),
+            location: non_empty(query.location),
+            category: non_empty(query.category),
+            tag: non_empty(query.tag),
+            person: non_empty(query.person),
+            from: non_empty(query.from),
+            to: non_empty(query.to),
+            fields: non_empty(query.fields),
+        };
+
+        let day = query
+            .day
+            .as_deref()
+            .map(|day| {
+                NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                    .map_err(|_| anyhow::anyhow!("`day` must be a date, like `2026-07-04`."))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            day,
+            location: query.location,
+            category: query.category,
+            tag: query.tag,
+            person: query.person,
+            from: query
+                .from
+                .as_deref()
+                .map(|time| parse_time("from", time))
+                .transpose()?,
+            to: query
+                .to
+                .as_deref()
+                .map(|time| parse_time("to", time))
+                .transpose()?,
+            fields: query.fields.as_deref().map(parse_fields).transpose()?,
+        })
+    }
+
+    // Whether this returns every field of every event, like the endpoint without query params.
+    pub fn is_empty(&self) -> bool {
+        self.day.is_none()
+            && self.location.is_none()
+            && self.category.is_none()
+            && self.tag.is_none()
+            && self.person.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+            && self.fields.is_none()
+    }
+
+    pub fn fields(&self) -> Option<Arc<[EventField]>> {
+        self.fields.clone()
+    }
+
+    // The query params for this filter in a canonical form, for the cache key. Filters that return
+    // the same thing have the same params, however they were written, and made-up params can't be
+    // used to get around the edge cache.
+    pub fn cache_key_params(&self) -> Vec<(&'static str, String)> {
+        let params = [
+            ("day", self.day.map(|day| day.to_string())),
+            ("location", self.location.clone()),
+            ("category", self.category.clone()),
+            ("tag", self.tag.clone()),
+            ("person", self.person.clone()),
+            ("from", self.from.map(|from| from.to_rfc3339())),
+            ("to", self.to.map(|to| to.to_rfc3339())),
+            (
+                "fields",
+                self.fields.as_ref().map(|fields| {
+                    fields
+                        .iter()
+                        .map(|field| field.name())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+            ),
+        ];
+
+        params
+            .into_iter()
+            .filter_map(|(param, value)| Some((param, value?)))
+            .collect()
+    }
+
+    // Whether to return this event. Days are in `timezone`, or in the UTC offset the event's start
+    // time was entered with if the con doesn't have a timezone.
+    pub fn matches(&self, event: &noco::Event, timezone: Option<Tz>) -> bool {
+        let matches_name = |filter: &Option<String>, value: Option<&String>| {
+            filter.is_none() || filter.as_ref() == value
+        };
+        let contains_name = |filter: &Option<String>, values: &[String]| {
+            filter.as_ref().is_none_or(|name| values.contains(name))
+        };
+
+        if !matches_name(&self.location, event.location.as_ref())
+            || !matches_name(&self.category, event.category.as_ref())
+            || !contains_name(&self.tag, &event.tags)
+            || !contains_name(&self.person, &event.people)
+        {
+            return false;
+        }
+
+        if self.day.is_none() && self.from.is_none() && self.to.is_none() {
+            return true;
+        }
+
+        // The cached events have already been checked, so this should always parse.
+        let Ok(start) = DateTime::parse_from_rfc3339(&event.start_time) else {
+            return false;
+        };
+        let end = event
+            .end_time
+            .as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .unwrap_or(start);
+
+        let start_day = match timezone {
+            Some(timezone) => start.with_timezone(&timezone).date_naive(),
+            None => start.date_naive(),
+        };
+
+        self.day.is_none_or(|day| start_day == day)
+            && self.from.is_none_or(|from| end >= from)
+            && self.to.is_none_or(|to| start < to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start_time: &str, end_time: Option<&str>) -> noco::Event {
+        noco::Event {
+            id: "1".to_string(),
+            name: "Opening ceremony".to_string(),
+            summary: None,
+            description: None,
+            start_time: start_time.to_string(),
+            end_time: end_time.map(str::to_string),
+            location: Some("Main Hall".to_string()),
+            category: Some("Ceremony".to_string()),
+            people: vec!["Sam".to_string()],
+            tags: vec!["All ages".to_string()],
+        }
+    }
+
+    fn filter(query: EventQuery) -> EventFilter {
+        EventFilter::parse(query).unwrap()
+    }
+
+    #[test]
+    fn filters_by_name() {
+        let event = event("2026-07-04T10:00:00Z", None);
+
+        assert!(filter(EventQuery::default()).matches(&event, None));
+        assert!(
+            filter(EventQuery {
+                location: Some("Main Hall".to_string()),
+                tag: Some("All ages".to_string()),
+                person: Some("Sam".to_string()),
+                ..Default::default()
+            })
+            .matches(&event, None)
+        );
+        assert!(
+            !filter(EventQuery {
+                category: Some("Panel".to_string()),
+                ..Default::default()
+            })
+            .matches(&event, None)
+        );
+    }
+
+    #[test]
+    fn filters_by_day_in_the_con_timezone() {
+        // This is the evening of July 3rd in New York.
+        let event = event("2026-07-04T01:00:00Z", None);
+        let on_the_3rd = filter(EventQuery {
+            day: Some("2026-07-03".to_string()),
+            ..Default::default()
+        });
+
+        assert!(on_the_3rd.matches(&event, Some(chrono_tz::America::New_York)));
+        assert!(!on_the_3rd.matches(&event, None));
+    }
+
+    #[test]
+    fn filters_by_overlapping_time_range() {
+        let event = event("2026-07-04T10:00:00Z", Some("2026-07-04T11:00:00Z"));
+        let range = |from: &str, to: &str| {
+            filter(EventQuery {
+                from: Some(from.to_string()),
+                to: Some(to.to_string()),
+                ..Default::default()
+            })
+        };
+
+        assert!(range("2026-07-04T10:30:00Z", "2026-07-04T12:00:00Z").matches(&event, None));
+        assert!(!range("2026-07-04T11:30:00Z", "2026-07-04T12:00:00Z").matches(&event, None));
+        assert!(!range("2026-07-04T09:00:00Z", "2026-07-04T10:00:00Z").matches(&event, None));
+    }
+
+    #[test]
+    fn treats_empty_params_as_absent() {
+        let empty = filter(EventQuery {
+            location: Some(String::new()),
+            fields: Some(String::new()),
+            ..Default::default()
+        });
+
+        assert!(empty.is_empty());
+        assert!(empty.cache_key_params().is_empty());
+    }
+
+    #[test]
+    fn cache_key_params_are_canonical() {
+        let params = filter(EventQuery {
+            day: Some("2026-07-04".to_string()),
+            from: Some("2026-07-04T10:00:00.000Z".to_string()),
+            fields: Some("start_time, name,name".to_string()),
+            ..Default::default()
+        })
+        .cache_key_params();
+
+        assert_eq!(
+            params,
+            [
+                ("day", "2026-07-04".to_string()),
+                ("from", "2026-07-04T10:00:00+00:00".to_string()),
+                ("fields", "id,name,start_time".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_fields() {
+        let fields = filter(EventQuery {
+            fields: Some("start_time, name,name".to_string()),
+            ..Default::default()
+        })
+        .fields()
+        .unwrap();
+
+        assert_eq!(
+            &*fields,
+            [EventField::Id, EventField::Name, EventField::StartTime]
+        );
+        assert!(
+            EventFilter::parse(EventQuery {
+                fields: Some("name,venue".to_string()),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            EventFilter::parse(EventQuery {
+                day: Some("Saturday".to_string()),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{ErrorResponse, Response},
+};
+
+use worker::console_warn;
+
+use crate::{auth::path_param, error::Error};
+
+// Cloudflare sets this to the IP address of the client connecting to the edge.
+const CLIENT_IP_HEADER: &str = "CF-Connecting-IP";
+
+// We drop windows which have ended once there are this many, so the map doesn't grow without
+// bound in a long-lived isolate.
+const MAX_TRACKED_WINDOWS: usize = 10_000;
+
+/// How many requests a client can make to a group of routes for one environment within a fixed
+/// window. Every route with the same budget shares it, so a client can't get a fresh budget by
+/// varying the rest of the path.
+///
+/// Like the inflight refresh lock in the store, these windows are tracked **within this isolate**.
+/// Under heavy load, Cloudflare may spread a client's requests across multiple isolates, so the
+/// effective limit can be a small multiple of the budget. That's fine; this is meant to stop a
+/// single client from burning through our KV write quota, not to enforce an exact limit.
+///
+/// Budgets are generous because attendees at a con often share a single IP address on the venue
+/// WiFi.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    name: &'static str,
+    limit: u32,
+    window: Duration,
+}
+
+impl Budget {
+    // Every one of these is a KV write. The app re-sends its subscription whenever it opens without
+    // a record of it, so this is sized for a venue's worth of attendees opening the app at once
+    // behind one IP address.
+    pub const SUBSCRIPTION_WRITES: Self = Self {
+        name: "subscription-writes",
+        limit: 600,
+        window: Duration::from_secs(60),
+    };
+
+    // This is shared by every public data route, and the app fetches several at once.
+    pub const PUBLIC_READS: Self = Self {
+        name: "public-reads",
+        limit: 3000,
+        window: Duration::from_secs(60),
+    };
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    index: u64,
+    count: u32,
+}
+
+#[derive(Debug, Default)]
+struct Windows {
+    by_key: HashMap<String, Window>,
+    // The window index we last dropped ended windows in, so we only scan the map once per window
+    // even if it's still full afterwards.
+    pruned_index: Option<u64>,
+}
+
+static WINDOWS: OnceLock<Mutex<Windows>> = OnceLock::new();
+
+fn windows() -> &'static Mutex<Windows> {
+    WINDOWS.get_or_init(|| Mutex::new(Windows::default()))
+}
+
+// This returns how long the client must wait if they're over budget.
+fn check_budget(
+    windows: &mut Windows,
+    key: String,
+    budget: Budget,
+    now: Duration,
+) -> Result<(), Duration> {
+    let window_millis = budget.window.as_millis() as u64;
+    let now_millis = now.as_millis() as u64;
+    let index = now_millis / window_millis;
+
+    if windows.by_key.len() >= MAX_TRACKED_WINDOWS && windows.pruned_index != Some(index) {
+        windows.by_key.retain(|_, window| window.index == index);
+        windows.pruned_index = Some(index);
+    }
+
+    let window = windows
+        .by_key
+        .entry(key)
+        .or_insert(Window { index, count: 0 });
+
+    if window.index != index {
+        *window = Window { index, count: 0 };
+    }
+
+    if window.count >= budget.limit {
+        let window_ends_at = (index + 1) * window_millis;
+        return Err(Duration::from_millis(window_ends_at - now_millis));
+    }
+
+    window.count += 1;
+
+    Ok(())
+}
+
+// Requests are limited per budget, per environment, and per client IP, so one busy con can't use up
+// the budget for another. Requests without a client IP, like in local development, aren't limited.
+pub async fn rate_limit_middleware(
+    State(budget): State<Budget>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ErrorResponse> {
+    let Some(client_ip) = request
+        .headers()
+        .get(CLIENT_IP_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return Ok(next.run(request).await);
+    };
+
+    let (mut parts, body) = request.into_parts();
+
+    let env_id = path_param(&mut parts, "env_id").await.unwrap_or_default();
+    let key = format!("{}|{env_id}|{client_ip}", budget.name);
+
+    let now = Duration::from_millis(chrono::Utc::now().timestamp_millis().max(0) as u64);
+
+    let result = check_budget(
+        &mut windows().lock().expect("rate limit lock poisoned"),
+        key,
+        budget,
+        now,
+    );
+
+    if let Err(retry_after) = result {
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(|matched_path| matched_path.as_str().to_string())
+            .unwrap_or_default();
+        console_warn!(
+            "Rate limited {client_ip} on {route} for {env_id} ({})",
+            budget.name
+        );
+
+        // Round up, so clients don't retry a moment too early.
+        let retry_after_secs = retry_after.as_millis().div_ceil(1000) as u64;
+        Err(Error::RateLimited(Duration::from_secs(retry_after_secs)))?;
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Budget = Budget {
+        name: "test",
+        limit: 2,
+        window: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn rejects_requests_over_budget_until_the_window_ends() {
+        let mut windows = Windows::default();
+        let start = Duration::from_secs(600);
+
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, start).is_ok());
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, start).is_ok());
+        assert_eq!(
+            check_budget(
+                &mut windows,
+                "a".into(),
+                BUDGET,
+                start + Duration::from_secs(15)
+            ),
+            Err(Duration::from_secs(45)),
+        );
+
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, start + BUDGET.window).is_ok());
+    }
+
+    #[test]
+    fn tracks_clients_separately() {
+        let mut windows = Windows::default();
+        let now = Duration::from_secs(600);
+
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, now).is_ok());
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, now).is_ok());
+        assert!(check_budget(&mut windows, "b".into(), BUDGET, now).is_ok());
+    }
+
+    #[test]
+    fn prunes_ended_windows_once_per_window() {
+        let mut windows = Windows::default();
+        let start = Duration::from_secs(600);
+
+        for i in 0..MAX_TRACKED_WINDOWS {
+            assert!(check_budget(&mut windows, i.to_string(), BUDGET, start).is_ok());
+        }
+
+        // The map is full of windows which are still current, so nothing is dropped, and we don't
+        // try again until the next window.
+        assert!(check_budget(&mut windows, "a".into(), BUDGET, start).is_ok());
+        assert_eq!(windows.by_key.len(), MAX_TRACKED_WINDOWS + 1);
+        assert_eq!(windows.pruned_index, Some(10));
+
+        assert!(check_budget(&mut windows, "b".into(), BUDGET, start + BUDGET.window).is_ok());
+        assert_eq!(windows.by_key.len(), 1);
+        assert_eq!(windows.pruned_index, Some(11));
+    }
+}
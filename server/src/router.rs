@@ -3,36 +3,48 @@ use std::{fmt, sync::Arc};
 use axum::{
     Json, Router,
     body::Body,
-    extract::{Path, State},
-    http::{self, StatusCode, Uri},
+    extract::{Extension, Path, Query, State, rejection::QueryRejection},
+    http::{self, HeaderMap, HeaderValue, StatusCode, Uri, header},
     middleware,
-    response::{ErrorResponse, NoContent},
-    routing::{delete, get, post, put},
+    response::{ErrorResponse, IntoResponse, NoContent},
+    routing::{delete, get, patch, post, put},
+};
+use worker::{
+    Bucket, Cache, Context, ObjectNamespace, Url, console_log, kv::KvStore, send::SendWrapper,
 };
-use worker::{Bucket, Cache, Context, console_log, kv::KvStore, send::SendWrapper};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     api::{
-        Announcement, DeleteSubscriptionRequest, Event, File, GetAliasResponse, GetAliasesResponse,
-        GetAnnouncementsResponse, GetConfigResponse, GetCurrentMigrationResponse,
+        BundleSection, DataResponseEnvelope, DeleteSubscriptionRequest, Event, GetAliasResponse,
+        GetAliasesResponse, GetAnnouncementsResponse, GetAuditResponse, GetBundleResponse,
+        GetCacheResponse, GetConfigHistoryResponse, GetConfigResponse, GetCurrentMigrationResponse,
         GetDomainEnvResponse, GetDomainResponse, GetEventsResponse, GetFilesResponse,
-        GetInfoResponse, GetLinkResponse, GetPagesResponse, Link, Page, PostApplyMigrationResponse,
-        PostBackupRequest, PostBaseRequest, PostRestoreBackupKind, PostRestoreBackupRequest,
+        GetInfoResponse, GetLinkResponse, GetLintResponse, GetOrganizerTokensResponse,
+        GetPagesResponse, GetValidationResponse, OrganizerTokenInfo, PostApplyMigrationResponse,
+        PostBackupRequest, PostBaseRequest, PostOrganizerTokenRequest, PostOrganizerTokenResponse,
+        PostRestoreBackupKind, PostRestoreBackupRequest, PostSubscriptionRequest, ProjectedEvent,
         PutAliasRequest, PutLinkResponse, PutTokenRequest,
     },
-    auth::{admin_auth_layer, noco_webhook_auth_layer},
-    cache::{cache_key_uri, get_cdn_cache, if_none_match_middleware, put_cdn_cache},
-    cf, config,
+    audit::{AuditSummary, audit_middleware},
+    auth::{Actor, AdminAccess, OrganizerToken, Role, admin_auth_layer, noco_webhook_auth_layer},
+    cache::{
+        accepts_brotli, cache_key_uri, cache_key_uri_with, content_hash, etag_hash, get_cdn_cache,
+        if_match, if_none_match_middleware, put_cdn_cache,
+    },
+    cf, changes, config, config_spec,
     cors::cors_layer,
-    env::{CONFIG_SPEC, Config, EnvDomain, EnvId, EnvName},
+    env::{Config, EnvDomain, EnvId, EnvName},
     error::Error,
     http::http_headers_from_object,
-    kv, neon,
+    kv, live, neon,
     noco::{self, ApiToken, MigrationState},
-    push, sql,
-    store::{MigrationChange, Store},
+    push,
+    query::{EventFilter, EventQuery},
+    rate_limit::{Budget, rate_limit_middleware},
+    sql,
+    store::{HookTable, MigrationChange, Store},
     url,
 };
 
@@ -54,6 +66,12 @@ pub struct AppState {
     pub kv: KvStore,
     pub bucket: SendWrapper<Bucket>,
     pub ctx: Arc<Context>,
+    // The Cloudflare datacenter handling this request, if we know.
+    pub colo: Option<String>,
+    // The Durable Objects that hold each environment's live update streams.
+    pub live: ObjectNamespace,
+    // The Durable Objects that hand out leases for background cache refreshes.
+    pub leases: ObjectNamespace,
 }
 
 impl fmt::Debug for AppState {
@@ -63,59 +81,156 @@ impl fmt::Debug for AppState {
 }
 
 pub fn new(state: AppState) -> Router {
-    // This service exposes two APIs: an unauthenticated "user" API for querying data that is used
-    // by the client app, and an authenticated "admin" API that is used to provision and manage
-    // environments.
-    Router::new()
-        // ADMIN API (AUTHENTICATED)
-        .route("/admin/env/{env_name}/links/{env_id}", put(put_link))
+    let state = Arc::new(state);
+
+    // Admin routes are grouped by what a caller needs to access them. Organizer tokens are scoped
+    // to one environment and carry roles, while the global admin API token can access everything.
+    let global_routes = Router::new()
+        .route("/admin/env/{env_name}/tokens", put(put_token))
+        .route(
+            "/admin/env/{env_name}/organizer-tokens",
+            post(post_organizer_token),
+        )
+        .route(
+            "/admin/env/{env_name}/organizer-tokens",
+            get(get_organizer_tokens),
+        )
+        .route(
+            "/admin/env/{env_name}/organizer-tokens/{token_id}",
+            delete(delete_organizer_token),
+        )
+        .route("/admin/aliases", get(get_aliases))
+        .route("/admin/aliases/{alias_id}", delete(delete_alias))
+        .route("/admin/aliases/{alias_id}", put(put_alias))
+        .route("/admin/audit", get(get_global_audit))
+        .admin_layers(&state.kv, AdminAccess::Global);
+
+    let read_only_routes = Router::new()
         .route("/admin/env/{env_name}/links", get(get_link))
-        .route("/admin/env/{env_name}/domain/{domain}", put(put_env_domain))
         .route("/admin/env/{env_name}/domain", get(get_env_domain))
+        .route(
+            "/admin/env/{env_name}/migrations/current",
+            get(get_current_migration),
+        )
+        .route("/admin/env/{env_name}/config", get(get_admin_config))
+        .route(
+            "/admin/env/{env_name}/config/history",
+            get(get_config_history),
+        )
+        .route("/admin/env/{env_name}/audit", get(get_env_audit))
+        .route("/admin/env/{env_name}/validation", get(get_validation))
+        .route("/admin/env/{env_name}/cache", get(get_cache_status))
+        .route("/admin/env/{env_name}/lint", get(get_admin_lint))
+        .route("/admin/config-spec", get(get_config_spec))
+        .admin_layers(&state.kv, AdminAccess::Env(Role::ReadOnly));
+
+    let cache_routes = Router::new()
+        .route("/admin/env/{env_name}/cache", delete(delete_cache))
+        .admin_layers(&state.kv, AdminAccess::Env(Role::Cache));
+
+    let config_routes = Router::new()
+        .route("/admin/env/{env_name}/links/{env_id}", put(put_link))
+        .route("/admin/env/{env_name}/domain/{domain}", put(put_env_domain))
         .route("/admin/env/{env_name}/domain", delete(delete_env_domain))
-        .route("/admin/env/{env_name}/tokens", put(put_token))
+        .route("/admin/env/{env_name}/config", put(put_admin_config))
+        .route("/admin/env/{env_name}/config", patch(patch_admin_config))
+        .route(
+            "/admin/env/{env_name}/config/revert/{version}",
+            post(post_revert_config),
+        )
+        .admin_layers(&state.kv, AdminAccess::Env(Role::Config));
+
+    let destructive_routes = Router::new()
         .route("/admin/env/{env_name}/bases", post(post_base))
         .route("/admin/env/{env_name}/bases", delete(delete_base))
         .route(
             "/admin/env/{env_name}/migrations/apply",
             post(post_apply_migration),
         )
-        .route(
-            "/admin/env/{env_name}/migrations/current",
-            get(get_current_migration),
-        )
         .route("/admin/env/{env_name}/backups", post(post_backup))
         .route(
             "/admin/env/{env_name}/backups/restore",
             post(post_restore_backup),
         )
-        .route("/admin/env/{env_name}/cache", delete(delete_cache))
-        .route("/admin/env/{env_name}/config", get(get_admin_config))
-        .route("/admin/env/{env_name}/config", put(put_admin_config))
-        .route("/admin/aliases", get(get_aliases))
-        .route("/admin/aliases/{alias_id}", delete(delete_alias))
-        .route("/admin/aliases/{alias_id}", put(put_alias))
-        .route("/admin/config-spec", get(get_config_spec))
-        .route_layer(admin_auth_layer())
-        // USER API (UNAUTHENTICATED)
+        .admin_layers(&state.kv, AdminAccess::Env(Role::Destructive));
+
+    let public_read_routes = Router::new()
         .route("/apps/{env_id}/events", get(get_events))
+        .route("/apps/{env_id}/events/changes", get(get_event_changes))
         .route("/apps/{env_id}/info", get(get_info))
         .route("/apps/{env_id}/pages", get(get_pages))
         .route("/apps/{env_id}/announcements", get(get_announcements))
         .route("/apps/{env_id}/files", get(get_files))
         .route("/apps/{env_id}/config", get(get_config))
+        .route("/apps/{env_id}/bundle", get(get_bundle))
+        .route("/apps/{env_id}/live", get(get_live))
+        .route("/apps/{env_id}/assets/{name}", get(get_asset))
+        .route("/apps/{env_id}/lint", get(get_public_lint))
+        .route("/aliases/{env_id}", get(get_alias))
+        .route("/domains/{domain}", get(get_domain_env))
+        .route_layer(middleware::from_fn_with_state(
+            Budget::PUBLIC_READS,
+            rate_limit_middleware,
+        ));
+
+    let subscription_routes = Router::new()
         .route("/apps/{env_id}/subscription", post(post_subscription))
         .route("/apps/{env_id}/subscription", delete(delete_subscription))
+        .route_layer(middleware::from_fn_with_state(
+            Budget::SUBSCRIPTION_WRITES,
+            rate_limit_middleware,
+        ));
+
+    // NocoDB calls these when its tables change, authenticated with the webhook token.
+    let noco_hook_routes = Router::new()
         .route(
             "/apps/{env_id}/hooks/announcement-created",
-            post(post_announcement_created).route_layer(noco_webhook_auth_layer()),
+            post(post_announcement_created),
         )
-        .route("/apps/{env_id}/assets/{name}", get(get_asset))
-        .route("/aliases/{env_id}", get(get_alias))
-        .route("/domains/{domain}", get(get_domain_env))
+        .route(
+            "/apps/{env_id}/hooks/announcement-updated",
+            post(post_announcement_updated),
+        )
+        .route(
+            "/apps/{env_id}/hooks/announcement-deleted",
+            post(post_announcement_deleted),
+        )
+        .route(
+            "/apps/{env_id}/hooks/table-changed/{table}",
+            post(post_table_changed),
+        )
+        .route_layer(noco_webhook_auth_layer(state.kv.clone()));
+
+    // This service exposes two APIs: an unauthenticated "user" API for querying data that is used
+    // by the client app, and an authenticated "admin" API that is used to provision and manage
+    // environments.
+    Router::new()
+        // ADMIN API (AUTHENTICATED)
+        .merge(global_routes)
+        .merge(read_only_routes)
+        .merge(cache_routes)
+        .merge(config_routes)
+        .merge(destructive_routes)
+        // USER API (UNAUTHENTICATED)
+        .merge(public_read_routes)
+        .merge(subscription_routes)
+        .merge(noco_hook_routes)
         .layer(middleware::from_fn(if_none_match_middleware))
         .layer(cors_layer())
-        .with_state(Arc::new(state))
+        .with_state(state)
+}
+
+trait AdminRouterExt {
+    fn admin_layers(self, kv: &KvStore, access: AdminAccess) -> Self;
+}
+
+impl AdminRouterExt for Router<Arc<AppState>> {
+    // The audit layer is added before the auth layer so that it runs inside it and can see who made
+    // the request.
+    fn admin_layers(self, kv: &KvStore, access: AdminAccess) -> Self {
+        self.route_layer(middleware::from_fn_with_state(kv.clone(), audit_middleware))
+            .route_layer(admin_auth_layer(kv.clone(), access))
+    }
 }
 
 #[axum::debug_handler]
@@ -131,6 +246,15 @@ async fn put_link(
         .as_ref()
         .is_some_and(|current| current == &new_env_id);
 
+    // Refuse to repoint an ID that is the current ID of a different environment.
+    if let Some(existing_owner) = kv::get_id_env(&state.kv, &new_env_id)
+        .await
+        .map_err(Error::Internal)?
+        && existing_owner.to_string() != env_name.to_string()
+    {
+        Err(Error::EnvIdInUse)?;
+    }
+
     if !already_current {
         kv::put_id_env(&state.kv, &new_env_id, &env_name)
             .await
@@ -341,6 +465,92 @@ async fn put_token(
     Ok(NoContent)
 }
 
+#[axum::debug_handler]
+async fn post_organizer_token(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+    Json(body): Json<PostOrganizerTokenRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let expires_at = body
+        .expires_at
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|err| Error::InvalidTokenExpiry(err.into()))?
+        .map(|expires_at| expires_at.to_utc());
+
+    let (token, bearer_token) = OrganizerToken::issue(env_name, body.name, body.roles, expires_at);
+
+    kv::put_organizer_token(&state.kv, &token)
+        .await
+        .map_err(Error::Internal)?;
+
+    let summary = AuditSummary(format!(
+        "Issued organizer token {} with roles: {}",
+        token.id,
+        join_roles(&token.roles),
+    ));
+
+    Ok((
+        Extension(summary),
+        Json(PostOrganizerTokenResponse {
+            id: token.id,
+            token: bearer_token,
+        }),
+    ))
+}
+
+#[axum::debug_handler]
+async fn get_organizer_tokens(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+) -> Result<Json<GetOrganizerTokensResponse>, ErrorResponse> {
+    let tokens = kv::list_organizer_tokens(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    Ok(Json(GetOrganizerTokensResponse {
+        tokens: tokens.into_iter().map(OrganizerTokenInfo::from).collect(),
+    }))
+}
+
+#[axum::debug_handler]
+async fn delete_organizer_token(
+    State(state): State<Arc<AppState>>,
+    Path((env_name, token_id)): Path<(EnvName, String)>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let mut token = kv::get_organizer_token(&state.kv, &token_id)
+        .await
+        .map_err(Error::Internal)?
+        .filter(|token| token.env_name.to_string() == env_name.to_string())
+        .ok_or(Error::NoOrganizerToken)?;
+
+    // Revoking a token that's already revoked keeps the original revocation time.
+    if token.revoked_at.is_none() {
+        token.revoked_at = Some(chrono::Utc::now().to_rfc3339());
+
+        kv::put_organizer_token(&state.kv, &token)
+            .await
+            .map_err(Error::Internal)?;
+    }
+
+    let summary = AuditSummary(format!(
+        "Revoked organizer token {} with roles: {}",
+        token.id,
+        join_roles(&token.roles),
+    ));
+
+    Ok((Extension(summary), NoContent))
+}
+
+fn join_roles(roles: &[Role]) -> String {
+    roles
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[axum::debug_handler]
 async fn post_base(
     State(state): State<Arc<AppState>>,
@@ -542,37 +752,343 @@ async fn delete_cache(
     Ok(NoContent)
 }
 
+// Writes to the config use optimistic concurrency control. The client must send back the ETag it
+// got when it read the config, and the write is rejected if the config has changed since. KV has
+// no compare-and-swap, so there is still a narrow window between the check and the write, but
+// that's acceptable for an API that's only used by a handful of operators.
+fn check_config_precondition(headers: &HeaderMap, current_etag: &str) -> Result<(), Error> {
+    let if_match_header = headers
+        .get(header::IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(Error::MissingIfMatch)?;
+
+    if !if_match(if_match_header, current_etag) {
+        return Err(Error::ConfigChanged);
+    }
+
+    Ok(())
+}
+
+fn config_etag_header(etag: &str) -> Result<[(header::HeaderName, HeaderValue); 1], Error> {
+    let value = HeaderValue::from_str(etag)
+        .map_err(anyhow::Error::from)
+        .map_err(Error::Internal)?;
+
+    Ok([(header::ETAG, value)])
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminConfigQuery {
+    // Sensitive values are redacted unless this is set. Only the global admin token may set it.
+    #[serde(default)]
+    include_sensitive: bool,
+}
+
+fn check_include_sensitive(query: &AdminConfigQuery, actor: &Actor) -> Result<(), Error> {
+    if query.include_sensitive && !matches!(actor, Actor::Admin) {
+        return Err(Error::SensitiveConfigForbidden);
+    }
+
+    Ok(())
+}
+
 #[axum::debug_handler]
 async fn get_admin_config(
+    State(state): State<Arc<AppState>>,
+    Extension(actor): Extension<Actor>,
+    Path(env_name): Path<EnvName>,
+    Query(query): Query<AdminConfigQuery>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    check_include_sensitive(&query, &actor)?;
+
+    let (config, etag) = kv::get_env_config_with_etag(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    // The ETag is always for the real config, so that a redacted config can be edited and written
+    // back.
+    let etag_header = config_etag_header(&etag)?;
+
+    let config = if query.include_sensitive {
+        config
+    } else {
+        config_spec::redact(&config).map_err(Error::Internal)?
+    };
+
+    Ok((etag_header, Json(config)))
+}
+
+async fn write_admin_config(
+    state: &AppState,
+    env_name: &EnvName,
+    config: &Config,
+    current: &Config,
+) -> Result<impl IntoResponse + use<>, Error> {
+    let config = config_spec::restore_redacted(config, current).map_err(Error::Internal)?;
+
+    let errors = config_spec::validate(&config, env_name, &state.bucket)
+        .await
+        .map_err(Error::Internal)?;
+
+    if !errors.is_empty() {
+        return Err(Error::InvalidConfig(errors));
+    }
+
+    let changed_keys = current
+        .diff(&config)
+        .map_err(Error::Internal)?
+        .into_iter()
+        .map(|change| change.key)
+        .collect::<Vec<_>>();
+
+    let etag = kv::put_env_config(&state.kv, env_name, &config)
+        .await
+        .map_err(Error::Internal)?;
+
+    let summary = AuditSummary(if changed_keys.is_empty() {
+        "Changed no config keys".to_string()
+    } else {
+        format!("Changed config keys: {}", changed_keys.join(", "))
+    });
+
+    Ok((Extension(summary), config_etag_header(&etag)?, NoContent))
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn put_admin_config(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+    headers: HeaderMap,
+    Json(config): Json<Config>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let (current, current_etag) = kv::get_env_config_with_etag(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    check_config_precondition(&headers, &current_etag)?;
+
+    Ok(write_admin_config(&state, &env_name, &config, &current).await?)
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn patch_admin_config(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+    headers: HeaderMap,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let (current, current_etag) = kv::get_env_config_with_etag(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    check_config_precondition(&headers, &current_etag)?;
+
+    let config = current
+        .merge_patch(&patch)
+        .map_err(Error::InvalidConfigPatch)?;
+
+    Ok(write_admin_config(&state, &env_name, &config, &current).await?)
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn get_config_history(
+    State(state): State<Arc<AppState>>,
+    Extension(actor): Extension<Actor>,
+    Path(env_name): Path<EnvName>,
+    Query(query): Query<AdminConfigQuery>,
+) -> Result<Json<GetConfigHistoryResponse>, ErrorResponse> {
+    check_include_sensitive(&query, &actor)?;
+
+    let mut versions = kv::list_config_history(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    if !query.include_sensitive {
+        for version in &mut versions {
+            version.config = config_spec::redact(&version.config).map_err(Error::Internal)?;
+        }
+    }
+
+    Ok(Json(GetConfigHistoryResponse { versions }))
+}
+
+// Reverting writes the old config as a new version, so the revert itself shows up in the history
+// and can be undone.
+#[axum::debug_handler]
+#[worker::send]
+async fn post_revert_config(
+    State(state): State<Arc<AppState>>,
+    Path((env_name, version)): Path<(EnvName, u32)>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let (current, current_etag) = kv::get_env_config_with_etag(&state.kv, &env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    check_config_precondition(&headers, &current_etag)?;
+
+    let config_version = kv::get_config_history_version(&state.kv, &env_name, version)
+        .await
+        .map_err(Error::Internal)?
+        .ok_or(Error::NoConfigVersion)?;
+
+    Ok(write_admin_config(&state, &env_name, &config_version.config, &current).await?)
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditQuery {
+    #[serde(default = "default_audit_limit")]
+    limit: u64,
+    cursor: Option<String>,
+}
+
+// KV won't list more than this many keys at once.
+const MAX_AUDIT_LIMIT: u64 = 1000;
+
+fn default_audit_limit() -> u64 {
+    100
+}
+
+async fn audit_response(
+    kv: &KvStore,
+    env_name: Option<&EnvName>,
+    query: AuditQuery,
+) -> Result<GetAuditResponse, Error> {
+    let (entries, cursor) = kv::list_audit_entries(
+        kv,
+        env_name,
+        query.limit.clamp(1, MAX_AUDIT_LIMIT),
+        query.cursor,
+    )
+    .await
+    .map_err(Error::Internal)?;
+
+    Ok(GetAuditResponse { entries, cursor })
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn get_env_audit(
     State(state): State<Arc<AppState>>,
     Path(env_name): Path<EnvName>,
-) -> Result<Json<Config>, ErrorResponse> {
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<GetAuditResponse>, ErrorResponse> {
     Ok(Json(
-        kv::get_env_config(&state.kv, &env_name)
+        audit_response(&state.kv, Some(&env_name), query).await?,
+    ))
+}
+
+#[axum::debug_handler]
+async fn get_validation(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+) -> Result<Json<GetValidationResponse>, ErrorResponse> {
+    Ok(Json(GetValidationResponse {
+        reports: kv::list_validation_reports(&state.kv, &env_name)
             .await
             .map_err(Error::Internal)?,
-    ))
+    }))
 }
 
+// How refreshing each cached dataset last went, to tell why an environment is serving old data.
 #[axum::debug_handler]
-async fn put_admin_config(
+async fn get_cache_status(
     State(state): State<Arc<AppState>>,
     Path(env_name): Path<EnvName>,
-    Json(config): Json<Config>,
-) -> Result<NoContent, ErrorResponse> {
-    kv::put_env_config(&state.kv, &env_name, &config)
+) -> Result<Json<GetCacheResponse>, ErrorResponse> {
+    Ok(Json(GetCacheResponse {
+        datasets: kv::list_cache_statuses(&state.kv, &env_name)
+            .await
+            .map_err(Error::Internal)?,
+    }))
+}
+
+async fn lint_response(store: &Store) -> Result<GetLintResponse, Error> {
+    let checked_at = chrono::Utc::now().to_rfc3339();
+    let issues = store.lint_schedule().await?;
+
+    Ok(GetLintResponse { checked_at, issues })
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn get_admin_lint(
+    State(state): State<Arc<AppState>>,
+    Path(env_name): Path<EnvName>,
+) -> Result<Json<GetLintResponse>, ErrorResponse> {
+    let store = Store::from_env_name(&state, env_name).await?;
+
+    Ok(Json(lint_response(&store).await?))
+}
+
+// Organizers can opt into making the lint report available without an admin token, so anyone
+// editing the schedule can check their work. It only describes the schedule itself, but it does
+// include hidden details like unscheduled events, so it's off by default.
+//
+// Anyone can request this, and linting reads every dataset, so we keep the report in the edge
+// cache like the data it's built from.
+#[axum::debug_handler]
+#[worker::send]
+async fn get_public_lint(
+    State(state): State<Arc<AppState>>,
+    uri: Uri,
+    Path(env_id): Path<EnvId>,
+) -> Result<http::Response<Body>, ErrorResponse> {
+    let env_name = kv::get_id_env(&state.kv, &env_id)
+        .await
+        .map_err(Error::Internal)?
+        .ok_or(Error::NoEnvId)?;
+
+    let config = kv::get_env_config(&state.kv, &env_name)
         .await
         .map_err(Error::Internal)?;
 
-    Ok(NoContent)
+    // We check this before the edge cache, so turning the public report off hides it right away
+    // rather than when the cached copy expires.
+    if !config.use_public_lint.unwrap_or(false) {
+        return Err(Error::LintNotPublic.into());
+    }
+
+    let cache = Cache::default();
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
+
+    if let Some(cached_response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
+        return Ok(cached_response);
+    };
+
+    let store = Store::from_env_name(&state, env_name.clone()).await?;
+    let cache_ttl = store.cache_ttl();
+
+    let mut worker_response =
+        worker::Response::try_from(Json(lint_response(&store).await?).into_response())
+            .map_err(|err| Error::Internal(anyhow::Error::from(err)))?;
+
+    let response_to_cache = worker_response
+        .cloned()
+        .map_err(|err| Error::Internal(anyhow::Error::from(err)))?;
+
+    state.ctx.wait_until(async move {
+        put_cdn_cache(&cache, env_name, cache_ttl, cache_uri, response_to_cache).await;
+    });
+
+    Ok(http::Response::from(worker_response))
 }
 
 #[axum::debug_handler]
-async fn get_config_spec() -> Result<Json<serde_json::Value>, ErrorResponse> {
-    Ok(serde_json::from_str::<serde_json::Value>(CONFIG_SPEC)
-        .map(Json)
-        .map_err(anyhow::Error::from)
-        .map_err(Error::Internal)?)
+#[worker::send]
+async fn get_global_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<GetAuditResponse>, ErrorResponse> {
+    Ok(Json(audit_response(&state.kv, None, query).await?))
+}
+
+#[axum::debug_handler]
+async fn get_config_spec() -> Json<&'static [config_spec::SpecEntry]> {
+    Json(config_spec::spec())
 }
 
 #[axum::debug_handler]
@@ -580,48 +1096,123 @@ async fn get_config_spec() -> Result<Json<serde_json::Value>, ErrorResponse> {
 async fn get_events(
     State(state): State<Arc<AppState>>,
     uri: Uri,
+    headers: HeaderMap,
     Path(env_id): Path<EnvId>,
+    query: Result<Query<EventQuery>, QueryRejection>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
+    let Query(query) = query.map_err(|err| Error::InvalidEventQuery(err.into()))?;
+    let filter = EventFilter::parse(query).map_err(Error::InvalidEventQuery)?;
+
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    // The cache key comes from the filter as we parsed it, so it can't disagree with what we return.
+    let cache_uri = cache_key_uri_with(&uri, filter.cache_key_params()).map_err(Error::Internal)?;
 
     if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(response);
     };
 
     let store = Store::from_env_id(&state, &env_id).await?;
+    let timezone = store.timezone();
+    let precompressed = filter.is_empty() && accepts_brotli(&headers);
 
     store
-        .get_events(cache_uri, |events| GetEventsResponse {
-            events: events
-                .into_iter()
-                .map(|event| Event {
-                    id: event.id,
-                    name: event.name,
-                    summary: event.summary,
-                    description: event.description,
-                    start_time: event.start_time,
-                    end_time: event.end_time,
-                    location: event.location,
-                    people: event.people,
-                    category: event.category,
-                    tags: event.tags,
-                })
-                .collect::<Vec<_>>(),
-        })
+        .get_events(
+            cache_uri,
+            move |events| GetEventsResponse {
+                events: events
+                    .into_iter()
+                    .filter(|event| filter.matches(event, timezone))
+                    .map(|event| ProjectedEvent {
+                        event: Event::from(event),
+                        fields: filter.fields(),
+                    })
+                    .collect::<Vec<_>>(),
+            },
+            precompressed,
+        )
+        .await
+        .map_err(Into::into)
+}
+
+#[derive(Debug, Deserialize)]
+struct EventChangesQuery {
+    // The version the client already has. This can be the ETag of the events endpoint.
+    since: Option<String>,
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn get_event_changes(
+    State(state): State<Arc<AppState>>,
+    uri: Uri,
+    Path(env_id): Path<EnvId>,
+    Query(query): Query<EventChangesQuery>,
+) -> Result<http::Response<Body>, ErrorResponse> {
+    let cache = Cache::default();
+    let cache_uri = cache_key_uri(&uri, &["since"]).map_err(Error::Internal)?;
+
+    if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
+        return Ok(response);
+    };
+
+    let store = Store::from_env_id(&state, &env_id).await?;
+
+    // The cache key ignores an empty `since`, so we must too.
+    let since = query
+        .since
+        .as_deref()
+        .filter(|since| !since.is_empty())
+        .map(etag_hash)
+        .map(str::to_string);
+
+    let snapshot = match &since {
+        Some(version) => kv::get_events_snapshot(&state.kv, store.env_name(), version)
+            .await
+            .map_err(Error::Internal)?,
+        None => None,
+    };
+
+    store
+        .get_events(
+            cache_uri,
+            move |events| changes::event_changes(events, since.as_deref(), snapshot.as_ref()),
+            false,
+        )
         .await
         .map_err(Into::into)
 }
 
+// A stream of the sections whose data changes while the client has it open, so they can refetch
+// right away instead of waiting to poll. Each message has the section's new ETag, so clients can
+// skip refetching data they already have.
+#[axum::debug_handler]
+#[worker::send]
+async fn get_live(
+    State(state): State<Arc<AppState>>,
+    Path(env_id): Path<EnvId>,
+) -> Result<http::Response<Body>, ErrorResponse> {
+    let env_name = kv::get_id_env(&state.kv, &env_id)
+        .await
+        .map_err(Error::Internal)?
+        .ok_or(Error::NoEnvId)?;
+
+    let response = live::connect(&state.live, &env_name)
+        .await
+        .map_err(|e| Error::Internal(e.into()))?;
+
+    Ok(http::Response::from(response))
+}
+
 #[axum::debug_handler]
 #[worker::send]
 async fn get_info(
     State(state): State<Arc<AppState>>,
     uri: Uri,
+    headers: HeaderMap,
     Path(env_id): Path<EnvId>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
 
     if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(response);
@@ -631,31 +1222,11 @@ async fn get_info(
     let env_name = store.env_name().to_string();
 
     store
-        .get_info(cache_uri, |info| GetInfoResponse {
-            env_name,
-            name: info.about.name.clone(),
-            description: info.about.description.clone(),
-            website_url: info.about.website_url.clone(),
-            links: info
-                .links
-                .into_iter()
-                .map(|link| Link {
-                    name: link.name,
-                    url: link.url,
-                })
-                .collect::<Vec<_>>(),
-            files: info
-                .about
-                .files
-                .into_iter()
-                .map(|file| File {
-                    id: file.id,
-                    name: file.name,
-                    media_type: file.media_type,
-                    signed_url: file.signed_url,
-                })
-                .collect::<Vec<_>>(),
-        })
+        .get_info(
+            cache_uri,
+            move |info| GetInfoResponse::new(env_name, info),
+            accepts_brotli(&headers),
+        )
         .await
         .map_err(Into::into)
 }
@@ -665,10 +1236,11 @@ async fn get_info(
 async fn get_pages(
     State(state): State<Arc<AppState>>,
     uri: Uri,
+    headers: HeaderMap,
     Path(env_id): Path<EnvId>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
 
     if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(response);
@@ -677,26 +1249,7 @@ async fn get_pages(
     let store = Store::from_env_id(&state, &env_id).await?;
 
     store
-        .get_pages(cache_uri, |pages| GetPagesResponse {
-            pages: pages
-                .into_iter()
-                .map(|page| Page {
-                    id: page.id,
-                    title: page.title,
-                    body: page.body,
-                    files: page
-                        .files
-                        .into_iter()
-                        .map(|file| File {
-                            id: file.id,
-                            name: file.name,
-                            media_type: file.media_type,
-                            signed_url: file.signed_url,
-                        })
-                        .collect::<Vec<_>>(),
-                })
-                .collect::<Vec<_>>(),
-        })
+        .get_pages(cache_uri, GetPagesResponse::from, accepts_brotli(&headers))
         .await
         .map_err(Into::into)
 }
@@ -706,10 +1259,11 @@ async fn get_pages(
 async fn get_announcements(
     State(state): State<Arc<AppState>>,
     uri: Uri,
+    headers: HeaderMap,
     Path(env_id): Path<EnvId>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
 
     if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(response);
@@ -718,28 +1272,11 @@ async fn get_announcements(
     let store = Store::from_env_id(&state, &env_id).await?;
 
     store
-        .get_announcements(cache_uri, |announcements| GetAnnouncementsResponse {
-            announcements: announcements
-                .into_iter()
-                .map(|announcement| Announcement {
-                    id: announcement.id,
-                    title: announcement.title,
-                    body: announcement.body,
-                    attachments: announcement
-                        .files
-                        .into_iter()
-                        .map(|file| File {
-                            id: file.id,
-                            name: file.name,
-                            media_type: file.media_type,
-                            signed_url: file.signed_url,
-                        })
-                        .collect::<Vec<_>>(),
-                    created_at: announcement.created_at,
-                    updated_at: announcement.updated_at,
-                })
-                .collect::<Vec<_>>(),
-        })
+        .get_announcements(
+            cache_uri,
+            GetAnnouncementsResponse::from,
+            accepts_brotli(&headers),
+        )
         .await
         .map_err(Into::into)
 }
@@ -749,10 +1286,11 @@ async fn get_announcements(
 async fn get_files(
     State(state): State<Arc<AppState>>,
     uri: Uri,
+    headers: HeaderMap,
     Path(env_id): Path<EnvId>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
 
     if let Some(response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(response);
@@ -761,17 +1299,7 @@ async fn get_files(
     let store = Store::from_env_id(&state, &env_id).await?;
 
     store
-        .get_files(cache_uri, |files| GetFilesResponse {
-            files: files
-                .into_iter()
-                .map(|file| File {
-                    id: file.id,
-                    name: file.name,
-                    media_type: file.media_type,
-                    signed_url: file.signed_url,
-                })
-                .collect::<Vec<_>>(),
-        })
+        .get_files(cache_uri, GetFilesResponse::from, accepts_brotli(&headers))
         .await
         .map_err(Into::into)
 }
@@ -786,16 +1314,20 @@ async fn get_config(
         .map_err(Error::Internal)?
         .ok_or(Error::NoEnvId)?;
 
-    let config = kv::get_env_config(&state.kv, &env_name)
+    Ok(Json(config_body(&state.kv, &env_name).await?))
+}
+
+async fn config_body(kv: &KvStore, env_name: &EnvName) -> Result<GetConfigResponse, Error> {
+    let config = kv::get_env_config(kv, env_name)
         .await
         .map_err(Error::Internal)?;
 
-    let app_domain = kv::get_env_domain(&state.kv, &env_name)
+    let app_domain = kv::get_env_domain(kv, env_name)
         .await
         .map_err(Error::Internal)?
         .map(|domain| domain.to_string());
 
-    Ok(Json(GetConfigResponse {
+    Ok(GetConfigResponse {
         app_domain,
         timezone: config.timezone,
         hide_announcements: config.hide_announcements,
@@ -821,6 +1353,238 @@ async fn get_config(
         pwa_icon_maskable_sizes: config.pwa_icon_maskable_sizes,
         use_push_notifications: config.use_push_notifications,
         notifications_icon_name: config.notifications_icon_name,
+        vapid_public_key: config::vapid_keys()
+            .map(|keys| push::Client::new(keys).vapid_public_key_b64().to_string()),
+    })
+}
+
+// The hash of each section the client already has, like `If-None-Match`. These can be the ETags
+// from each section's own endpoint.
+#[derive(Debug, Deserialize)]
+struct BundleQuery {
+    config: Option<String>,
+    info: Option<String>,
+    events: Option<String>,
+    pages: Option<String>,
+    announcements: Option<String>,
+    files: Option<String>,
+}
+
+// The endpoint a section of the bundle comes from. Background refreshes update that endpoint's
+// edge cache.
+fn section_uri(uri: &Uri, env_id: &EnvId, section: &str) -> Result<Uri, Error> {
+    let mut url = Url::parse(&uri.to_string()).map_err(|err| Error::Internal(err.into()))?;
+    url.set_path(&format!("/apps/{env_id}/{section}"));
+    url.set_query(None);
+    url.as_str()
+        .parse()
+        .map_err(|err: http::uri::InvalidUri| Error::Internal(err.into()))
+}
+
+fn bundle_section<T: Serialize>(
+    envelope: DataResponseEnvelope<T>,
+    known_hash: Option<&str>,
+) -> BundleSection<T> {
+    let hash = content_hash(&envelope.value);
+    let unchanged = known_hash.map(etag_hash) == Some(hash.as_str());
+
+    BundleSection {
+        stale: envelope.stale,
+        value: (!unchanged).then_some(envelope.value),
+        hash,
+    }
+}
+
+fn json_bundle_section<T: Serialize>(
+    envelope: DataResponseEnvelope<T>,
+    known_hash: Option<&str>,
+) -> Result<BundleSection<serde_json::Value>, Error> {
+    let envelope = DataResponseEnvelope {
+        stale: envelope.stale,
+        value: serde_json::to_value(envelope.value).map_err(|err| Error::Internal(err.into()))?,
+    };
+
+    Ok(bundle_section(envelope, known_hash))
+}
+
+#[derive(Debug, Deserialize)]
+struct CachedSection {
+    stale: bool,
+    value: serde_json::Value,
+}
+
+// A section from its endpoint's edge cache, if it's there. The cached response's ETag is the
+// section's hash, so we only read the body if the client doesn't already have it.
+async fn edge_cached_section(
+    cache: &Cache,
+    uri: Uri,
+    known_hash: Option<&str>,
+) -> Result<Option<BundleSection<serde_json::Value>>, Error> {
+    let Some(response) = get_cdn_cache(cache, uri).await? else {
+        return Ok(None);
+    };
+
+    let Some(hash) = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag_hash(etag).to_string())
+    else {
+        return Ok(None);
+    };
+
+    if known_hash.map(etag_hash) == Some(hash.as_str()) {
+        return Ok(Some(BundleSection {
+            hash,
+            stale: false,
+            value: None,
+        }));
+    }
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|err| Error::Internal(err.into()))?;
+    let section = serde_json::from_slice::<CachedSection>(&body)
+        .map_err(|err| Error::Internal(err.into()))?;
+
+    Ok(Some(BundleSection {
+        hash,
+        stale: section.stale,
+        value: Some(section.value),
+    }))
+}
+
+// Each section comes from its own endpoint's edge cache if it's there, so a burst of clients
+// starting up doesn't go to the persistent cache and kick off a refresh for every section. The
+// bundle itself isn't edge cached, because it depends on which sections the client already has.
+#[axum::debug_handler]
+#[worker::send]
+async fn get_bundle(
+    State(state): State<Arc<AppState>>,
+    uri: Uri,
+    Path(env_id): Path<EnvId>,
+    Query(query): Query<BundleQuery>,
+) -> Result<Json<GetBundleResponse>, ErrorResponse> {
+    let cache = Cache::default();
+
+    let (info, events, pages, announcements, files) = futures::try_join!(
+        edge_cached_section(
+            &cache,
+            section_uri(&uri, &env_id, "info")?,
+            query.info.as_deref()
+        ),
+        edge_cached_section(
+            &cache,
+            section_uri(&uri, &env_id, "events")?,
+            query.events.as_deref()
+        ),
+        edge_cached_section(
+            &cache,
+            section_uri(&uri, &env_id, "pages")?,
+            query.pages.as_deref()
+        ),
+        edge_cached_section(
+            &cache,
+            section_uri(&uri, &env_id, "announcements")?,
+            query.announcements.as_deref()
+        ),
+        edge_cached_section(
+            &cache,
+            section_uri(&uri, &env_id, "files")?,
+            query.files.as_deref()
+        ),
+    )?;
+
+    let store = Store::from_env_id(&state, &env_id).await?;
+    let env_name = store.env_name().to_string();
+
+    // Sections that missed the edge cache are read the same way their own endpoint reads them.
+    let (config, info, events, pages, announcements, files) = futures::try_join!(
+        config_body(&state.kv, store.env_name()),
+        async {
+            match info {
+                Some(section) => Ok(section),
+                None => json_bundle_section(
+                    store
+                        .get_info_value(section_uri(&uri, &env_id, "info")?, move |info| {
+                            GetInfoResponse::new(env_name, info)
+                        })
+                        .await?,
+                    query.info.as_deref(),
+                ),
+            }
+        },
+        async {
+            match events {
+                Some(section) => Ok(section),
+                None => json_bundle_section(
+                    store
+                        .get_events_value(
+                            section_uri(&uri, &env_id, "events")?,
+                            GetEventsResponse::from,
+                        )
+                        .await?,
+                    query.events.as_deref(),
+                ),
+            }
+        },
+        async {
+            match pages {
+                Some(section) => Ok(section),
+                None => json_bundle_section(
+                    store
+                        .get_pages_value(
+                            section_uri(&uri, &env_id, "pages")?,
+                            GetPagesResponse::from,
+                        )
+                        .await?,
+                    query.pages.as_deref(),
+                ),
+            }
+        },
+        async {
+            match announcements {
+                Some(section) => Ok(section),
+                None => json_bundle_section(
+                    store
+                        .get_announcements_value(
+                            section_uri(&uri, &env_id, "announcements")?,
+                            GetAnnouncementsResponse::from,
+                        )
+                        .await?,
+                    query.announcements.as_deref(),
+                ),
+            }
+        },
+        async {
+            match files {
+                Some(section) => Ok(section),
+                None => json_bundle_section(
+                    store
+                        .get_files_value(
+                            section_uri(&uri, &env_id, "files")?,
+                            GetFilesResponse::from,
+                        )
+                        .await?,
+                    query.files.as_deref(),
+                ),
+            }
+        },
+    )?;
+
+    // The config isn't cached, so it's never stale.
+    let config = DataResponseEnvelope {
+        stale: false,
+        value: config,
+    };
+
+    Ok(Json(GetBundleResponse {
+        config: bundle_section(config, query.config.as_deref()),
+        info,
+        events,
+        pages,
+        announcements,
+        files,
     }))
 }
 
@@ -829,14 +1593,58 @@ async fn get_config(
 async fn post_subscription(
     State(state): State<Arc<AppState>>,
     Path(env_id): Path<EnvId>,
-    Json(subscription): Json<push::Subscription>,
+    headers: HeaderMap,
+    Json(body): Json<PostSubscriptionRequest>,
 ) -> Result<NoContent, ErrorResponse> {
+    body.subscription
+        .validate()
+        .map_err(Error::InvalidSubscription)?;
+
     let env_name = kv::get_id_env(&state.kv, &env_id)
         .await
         .map_err(Error::Internal)?
         .ok_or(Error::NoEnvId)?;
 
-    kv::put_subscription(&state.kv, &env_name, &subscription)
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    // We can only send to subscriptions made against a VAPID key we still hold. If the client
+    // didn't say which key it used, we keep the one we already have on record, or otherwise work
+    // it out when sending, like for subscriptions stored before we recorded the key.
+    let vapid_public_key = match (config::vapid_keys(), body.vapid_public_key) {
+        (Some(keys), Some(public_key)) => Some(
+            keys.find(&public_key)
+                .ok_or_else(|| {
+                    Error::InvalidSubscription(anyhow::anyhow!(
+                        "subscription was created with an unknown VAPID key"
+                    ))
+                })?
+                .public_key_b64()
+                .to_string(),
+        ),
+        (_, None) => None,
+        (None, public_key) => public_key,
+    };
+
+    let existing = kv::get_subscription(&state.kv, &env_name, &body.subscription.id())
+        .await
+        .map_err(Error::Internal)?;
+
+    let now = chrono::Utc::now();
+
+    // Browsers re-register their subscription each time the app is opened, which mustn't reset
+    // how deliveries to it have gone.
+    let record = match existing {
+        Some(mut record) => {
+            record.seen_again(body.subscription, vapid_public_key, user_agent, now);
+            record
+        }
+        None => push::SubscriptionRecord::new(body.subscription, vapid_public_key, user_agent, now),
+    };
+
+    kv::put_subscription(&state.kv, &env_name, &record)
         .await
         .map_err(Error::Internal)?;
 
@@ -874,28 +1682,122 @@ struct NocoAnnouncementWebhook {
 struct NocoAnnouncementWebhookData {
     #[serde(default)]
     rows: Vec<NocoAnnouncementRow>,
+    // Only sent for updates.
+    #[serde(default)]
+    previous_rows: Vec<NocoAnnouncementRow>,
 }
 
 #[derive(Debug, Deserialize)]
 struct NocoAnnouncementRow {
+    #[serde(rename = "ID", default)]
+    id: Option<u32>,
     #[serde(rename = "Title")]
     title: String,
     #[serde(rename = "Announcement", default)]
     body: Option<String>,
 }
 
-#[axum::debug_handler]
-#[worker::send]
-async fn post_announcement_created(
-    State(state): State<Arc<AppState>>,
-    Path(env_id): Path<EnvId>,
-    Json(webhook): Json<NocoAnnouncementWebhook>,
-) -> Result<NoContent, ErrorResponse> {
-    let Some(vapid) = config::vapid_key() else {
-        return Err(StatusCode::SERVICE_UNAVAILABLE.into());
+// Build the notification for an announcement. `cached` is the announcement as we just cached it,
+// if it made it into the cache.
+fn announcement_payload(
+    row: &NocoAnnouncementRow,
+    cached: Option<&noco::Announcement>,
+    icon: Option<String>,
+    edited: bool,
+) -> push::Payload {
+    let title = if edited {
+        format!("Updated: {}", row.title)
+    } else {
+        row.title.clone()
+    };
+    let body = push::markdown_to_plain_text(row.body.as_deref().unwrap_or(""));
+
+    // We only deep link to the announcement once we know it's in the cache; otherwise the user
+    // would land on a page for an announcement the app can't find yet. The announcements list is
+    // the next best thing, since the service worker nudges open clients to refetch it.
+    let Some(announcement) = cached else {
+        return push::Payload {
+            title,
+            body,
+            url: "/announcements".into(),
+            icon,
+            tag: row.id.map(|id| push::announcement_tag(&id.to_string())),
+            renotify: true,
+            image: None,
+            badge: None,
+            actions: Vec::new(),
+            timestamp: None,
+        };
     };
 
-    let env_name = kv::get_id_env(&state.kv, &env_id)
+    let url = format!("/announcements/{}", announcement.id);
+
+    let image = announcement
+        .files
+        .iter()
+        .find(|file| file.media_type.starts_with("image/"))
+        .map(|file| file.signed_url.clone());
+
+    let timestamp = announcement
+        .updated_at
+        .as_deref()
+        .unwrap_or(&announcement.created_at)
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .ok()
+        .map(|time| time.timestamp_millis());
+
+    push::Payload {
+        title,
+        body,
+        url: url.clone(),
+        icon,
+        tag: Some(push::announcement_tag(&announcement.id)),
+        // An edited announcement replaces the notification for the original, but attendees should
+        // still be alerted that something changed.
+        renotify: true,
+        image,
+        badge: None,
+        actions: vec![
+            push::Action {
+                action: "view",
+                title: "View",
+                url,
+            },
+            push::Action {
+                action: "all",
+                title: "All announcements",
+                url: "/announcements".into(),
+            },
+        ],
+        timestamp,
+    }
+}
+
+// Refresh the announcements cache, then push a notification for each of `rows` in the background.
+// Edits are sent as corrections, which replace the notification for the original on the device.
+async fn refresh_and_notify_announcements(
+    state: &Arc<AppState>,
+    env_id: &EnvId,
+    vapid: Option<push::VapidKeys>,
+    rows: &[&NocoAnnouncementRow],
+    edited: bool,
+) -> Result<(), ErrorResponse> {
+    // Re-seed the persistent cache from NocoDB and purge the edge cache for this environment so the
+    // change is available immediately.
+    let announcements = Store::from_env_id(state, env_id)
+        .await?
+        .refresh_announcements_cache()
+        .await?;
+
+    let Some(vapid) = vapid else {
+        return Ok(());
+    };
+
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let env_name = kv::get_id_env(&state.kv, env_id)
         .await
         .map_err(Error::Internal)?
         .ok_or(Error::NoEnvId)?;
@@ -905,7 +1807,7 @@ async fn post_announcement_created(
         .map_err(Error::Internal)?;
 
     if !env_config.use_push_notifications.unwrap_or(true) {
-        return Ok(NoContent);
+        return Ok(());
     }
 
     let icon = env_config
@@ -922,41 +1824,98 @@ async fn post_announcement_created(
             )
         });
 
-    // Re-seed the persistent cache from NocoDB and purge the edge cache for this environment so the
-    // new announcement is available immediately.
-    Store::from_env_id(&state, &env_id)
-        .await?
-        .refresh_announcements_cache()
-        .await?;
-
-    let mut payloads: Vec<Vec<u8>> = Vec::with_capacity(webhook.data.rows.len());
-    for row in &webhook.data.rows {
-        let body = push::markdown_to_plain_text(row.body.as_deref().unwrap_or(""));
-        let payload = push::Payload {
-            title: &row.title,
-            // We send the user to the announcements list page rather than the page for the specific
-            // announcement because the new announcement won't be in their local cache by the time
-            // they get there. However, refreshing the cache ensures the new announcement should
-            // appear quickly.
-            url: "/announcements".into(),
-            body,
-            icon: icon.clone(),
-        };
+    let mut payloads: Vec<Vec<u8>> = Vec::with_capacity(rows.len());
+    for row in rows {
+        let cached = row.id.and_then(|id| {
+            announcements
+                .iter()
+                .find(|announcement| announcement.id == id.to_string())
+        });
+        let payload = announcement_payload(row, cached, icon.clone(), edited);
         payloads.push(serde_json::to_vec(&payload).map_err(|e| Error::Internal(e.into()))?);
     }
 
     let kv = state.kv.clone();
-    let env_name_owned = env_name.clone();
     let client = push::Client::new(vapid);
     state.ctx.wait_until(async move {
         for payload in payloads {
-            if let Err(e) = push::push_notifications(&kv, &env_name_owned, &client, &payload).await
-            {
+            if let Err(e) = push::push_notifications(&kv, &env_name, &client, &payload).await {
                 worker::console_warn!("Announcement push fan-out failed: {e}");
             }
         }
     });
 
+    Ok(())
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn post_announcement_created(
+    State(state): State<Arc<AppState>>,
+    Path(env_id): Path<EnvId>,
+    Json(webhook): Json<NocoAnnouncementWebhook>,
+) -> Result<NoContent, ErrorResponse> {
+    let Some(vapid) = config::vapid_keys() else {
+        return Err(StatusCode::SERVICE_UNAVAILABLE.into());
+    };
+
+    let rows = webhook.data.rows.iter().collect::<Vec<_>>();
+
+    refresh_and_notify_announcements(&state, &env_id, Some(vapid), &rows, false).await?;
+
+    Ok(NoContent)
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn post_announcement_updated(
+    State(state): State<Arc<AppState>>,
+    Path(env_id): Path<EnvId>,
+    Json(webhook): Json<NocoAnnouncementWebhook>,
+) -> Result<NoContent, ErrorResponse> {
+    // Only send a correction if something attendees can see in the notification changed. Fixing a
+    // typo in an attachment name shouldn't buzz everyone's phone.
+    let rows = webhook
+        .data
+        .rows
+        .iter()
+        .filter(|row| {
+            !webhook.data.previous_rows.iter().any(|previous| {
+                previous.id == row.id && previous.title == row.title && previous.body == row.body
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Unlike a new announcement, an edit is still worth caching even if we can't push it.
+    refresh_and_notify_announcements(&state, &env_id, config::vapid_keys(), &rows, true).await?;
+
+    Ok(NoContent)
+}
+
+// Notifications for a deleted announcement stay on devices, but at least the announcement
+// disappears from the app right away.
+#[axum::debug_handler]
+#[worker::send]
+async fn post_announcement_deleted(
+    State(state): State<Arc<AppState>>,
+    Path(env_id): Path<EnvId>,
+) -> Result<NoContent, ErrorResponse> {
+    refresh_and_notify_announcements(&state, &env_id, None, &[], false).await?;
+
+    Ok(NoContent)
+}
+
+#[axum::debug_handler]
+#[worker::send]
+async fn post_table_changed(
+    State(state): State<Arc<AppState>>,
+    Path((env_id, table)): Path<(EnvId, HookTable)>,
+) -> Result<NoContent, ErrorResponse> {
+    Store::from_env_id(&state, &env_id)
+        .await?
+        .refresh_table_cache(table)
+        .await?;
+
     Ok(NoContent)
 }
 
@@ -968,7 +1927,7 @@ async fn get_asset(
     Path((env_id, name)): Path<(EnvId, String)>,
 ) -> Result<http::Response<Body>, ErrorResponse> {
     let cache = Cache::default();
-    let cache_uri = cache_key_uri(&uri).map_err(Error::Internal)?;
+    let cache_uri = cache_key_uri(&uri, &[]).map_err(Error::Internal)?;
 
     if let Some(cached_response) = get_cdn_cache(&cache, cache_uri.clone()).await? {
         return Ok(cached_response);
@@ -8,18 +8,23 @@ use axum::{
     http::{self, Uri},
     response::IntoResponse,
 };
-use serde::Serialize;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 use worker::kv::KvStore;
-use worker::{Cache, Context, console_error, console_log, console_warn};
+use worker::{Cache, Context, ObjectNamespace, console_error, console_log, console_warn};
 
-use crate::api::{DataResponseEnvelope, PostBackupKind};
-use crate::cache::{EtagJson, put_cdn_cache};
+use crate::api::{CacheStatus, DataResponseEnvelope, PostBackupKind, RefreshFailure};
+use crate::cache::{
+    CacheSource, EtagJson, precompressed_response, put_cdn_cache, set_cache_headers,
+};
 use crate::env::{Config, EnvId, EnvName};
 use crate::error::Error;
+use crate::live::{self, LiveMessage};
 use crate::neon::BackupSnapshot;
 use crate::noco::{self, BaseId, ExistingMigrationState, MigrationState, TableIds};
 use crate::router::AppState;
-use crate::{cf, config, kv, url};
+use crate::{cf, changes, config, kv, lease, url, warm};
 use crate::{
     neon::Client as NeonClient,
     noco::Client as NocoClient,
@@ -32,9 +37,9 @@ use crate::{
 /// key when it completes.
 ///
 /// This locking mechanism only works **within this isolate**. Under heavy load, Cloudflare may
-/// spin up multiple isolates to handle requests. That may result in multiple concurrent background
-/// refreshes for the same key, but for our use-case it would likely be on the order of "a few" and
-/// not "a few hundred", which is acceptable.
+/// spin up multiple isolates to handle requests, each of which would refresh the same key. To
+/// avoid that, the background task also takes a lease from a Durable Object, keyed by datacenter,
+/// before going upstream. This set remains the fallback when the lease can't be used.
 ///
 /// We actually *do not* want a global lock across all isolates across all datacenters, because the
 /// CDN cache (what we call the "edge cache" below) is scoped per-datacenter, so we would need each
@@ -44,6 +49,10 @@ use crate::{
 /// only needed to satisfy Send/Sync bounds.
 static INFLIGHT_REFRESHES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
 
+/// How long a refresh lease lasts if the isolate holding it never releases it. This is comfortably
+/// longer than a refresh from NocoDB should take.
+const REFRESH_LEASE_TTL: Duration = Duration::from_secs(60);
+
 fn inflight_refreshes() -> &'static Mutex<HashSet<String>> {
     INFLIGHT_REFRESHES.get_or_init(|| Mutex::new(HashSet::new()))
 }
@@ -56,6 +65,9 @@ pub struct Store {
     env_name: EnvName,
     base_id: BaseId,
     env_config: Config,
+    colo: Option<String>,
+    live: ObjectNamespace,
+    leases: ObjectNamespace,
 }
 
 impl fmt::Debug for Store {
@@ -73,7 +85,7 @@ pub struct MigrationChange {
     pub new_version: noco::Version,
 }
 
-// This macro generates a method on the `Store` for fetching data from NocoDB with caching.
+// This macro generates methods on the `Store` for fetching data from NocoDB with caching.
 //
 // We're using the Cloudflare cache API with a short TTL (configurable per-environment, but likely
 // on the order of seconds) to reduce the load on the upstream NocoDB instance. We'll call this the
@@ -96,24 +108,86 @@ pub struct MigrationChange {
 macro_rules! get_data {
     {
         fn_name: $fn_name:ident,
+        value_fn_name: $value_fn_name:ident,
+        body_fn_name: $body_fn_name:ident,
         type_name: $type_name:ty,
         get_api_fn: $get_api_fn:path,
         get_cached_fn: $get_cached_fn:path,
         put_cached_fn: $put_cached_fn:path,
         cache_key: $cache_key:expr,
     } => {
+        // If `precompressed` is set, we may send the response body we stored compressed alongside
+        // the cached value instead of calling `to_body`. Only set it when `to_body` builds the
+        // endpoint's unfiltered body and the client accepts brotli.
         #[worker::send]
-        pub async fn $fn_name<T, F>(&self, uri: Uri, to_body: F) -> Result<http::Response<Body>, Error>
+        pub async fn $fn_name<T, F>(&self, uri: Uri, to_body: F, precompressed: bool) -> Result<http::Response<Body>, Error>
         where
             T: Serialize + Clone + 'static,
             F: FnOnce($type_name) -> T + Clone + 'static,
         {
+            let cached = self.$body_fn_name(uri, to_body, precompressed).await?;
 
-            let cached_value = match $get_cached_fn(&self.kv, &self.env_name).await {
-                Ok(Some(value)) => {
-                    console_log!("Returning stale {} from cache.", $cache_key);
-                    Some(value)
-                }
+            let mut response = match cached.body {
+                CachedBody::Value(envelope) => EtagJson(envelope).into_response(),
+                CachedBody::Precompressed(body) => precompressed_response(body),
+            };
+            set_cache_headers(response.headers_mut(), cached.source, cached.cached_at);
+
+            Ok(response)
+        }
+
+        // The same, but returning the body itself rather than a response. `uri` is still the
+        // endpoint whose edge cache a background refresh updates, so `to_body` must build the
+        // same body that endpoint does.
+        #[worker::send]
+        pub async fn $value_fn_name<T, F>(&self, uri: Uri, to_body: F) -> Result<DataResponseEnvelope<T>, Error>
+        where
+            T: Serialize + Clone + 'static,
+            F: FnOnce($type_name) -> T + Clone + 'static,
+        {
+            match self.$body_fn_name(uri, to_body, false).await?.body {
+                CachedBody::Value(envelope) => Ok(envelope),
+                CachedBody::Precompressed(_) => Err(Error::Internal(anyhow::anyhow!(
+                    "Got a precompressed {} body we didn't ask for.",
+                    $cache_key,
+                ))),
+            }
+        }
+
+        #[worker::send]
+        async fn $body_fn_name<T, F>(&self, uri: Uri, to_body: F, precompressed: bool) -> Result<CachedResponse<DataResponseEnvelope<T>>, Error>
+        where
+            T: Serialize + Clone + 'static,
+            F: FnOnce($type_name) -> T + Clone + 'static,
+        {
+            let (cached_entry, warm_interval) = futures::join!(
+                $get_cached_fn(&self.kv, &self.env_name),
+                kv::get_warm_interval(&self.kv, &self.env_name),
+            );
+
+            let fresh_interval = warm::fresh_interval(warm_interval.unwrap_or_else(|e| {
+                console_warn!("Failed getting the warm interval from KV: {}", e);
+                None
+            }));
+
+            let cached_value = match cached_entry {
+                // The precompressed body is marked stale, so we can't send it for a fresh entry.
+                Ok(Some(entry)) => match (is_recently_cached(entry.cached_at(), fresh_interval), entry.precompressed_body().filter(|_| precompressed)) {
+                    (false, Some(body)) => {
+                        console_log!("Returning stale precompressed {} from cache.", $cache_key);
+                        Some((CachedBody::Precompressed(body.to_vec()), entry.cached_at(), false))
+                    }
+                    (fresh, _) => match entry.value() {
+                        Ok(value) => {
+                            console_log!("Returning {} {} from cache.", if fresh { "fresh" } else { "stale" }, $cache_key);
+                            Some((CachedBody::Value(value), entry.cached_at(), fresh))
+                        }
+                        Err(e) => {
+                            console_warn!("Failed decoding cached {} from KV: {}", $cache_key, e);
+                            None
+                        }
+                    },
+                },
                 Ok(None) => {
                     None
                 }
@@ -130,39 +204,62 @@ macro_rules! get_data {
             let base_id_for_upstream = self.base_id.clone();
 
             // A request to get the most recent data from NocoDB.
+            // This also returns when it started, so we can record how long the refresh took.
             let upstream_request = async move {
+                let started_at = chrono::Utc::now();
+
                 match Self::get_table_ids(&kv_for_upstream, &env_name_for_upstream, &noco_client_for_upstream, &base_id_for_upstream).await {
                     Ok(table_ids) => {
                         match $get_api_fn(&noco_client_for_upstream, &table_ids)
                             .await
                         {
-                            Ok(value) => Some(value),
+                            Ok(validated) => Some((
+                                record_validation(&kv_for_upstream, &env_name_for_upstream, $cache_key, validated).await,
+                                started_at,
+                            )),
                             Err(e) => {
                                 console_warn!("Failed getting {} from NocoDB: {}", $cache_key, e);
+                                record_refresh_failure(&kv_for_upstream, &env_name_for_upstream, $cache_key, &e).await;
                                 None
                             }
                         }
                     },
                     Err(e) => {
                         console_warn!("Failed getting table IDs from NocoDB: {}", e);
+                        record_refresh_failure(&kv_for_upstream, &env_name_for_upstream, $cache_key, &e).await;
                         None
                     }
                 }
             };
 
             let kv_for_cache = self.kv.clone();
+            let live_for_cache = self.live.clone();
             let env_name_for_cache = self.env_name.clone();
             let env_name_for_cdn = self.env_name.clone();
             let cache_ttl = self.cache_ttl();
 
             // Refresh both the edge cache and the persistent cache.
-            let put_cache = async move |value: $type_name, body: T| {
+            let put_cache = async move |value: $type_name, body: T, started_at: DateTime<Utc>| {
                 console_log!("Caching latest {} from NocoDB.", $cache_key);
 
-                if let Err(e) = $put_cached_fn(&kv_for_cache, &env_name_for_cache, &value).await {
+                let put_result = cache_refreshed(
+                    &kv_for_cache,
+                    &env_name_for_cache,
+                    $cache_key,
+                    started_at,
+                    $put_cached_fn(&kv_for_cache, &env_name_for_cache, &value),
+                ).await;
+
+                if let Err(e) = put_result {
                     console_warn!("Failed putting {} in KV cache: {}", $cache_key, e);
                 }
 
+                live::notify(
+                    &live_for_cache,
+                    &env_name_for_cache,
+                    &[LiveMessage::of(&env_name_for_cache, &value)],
+                ).await;
+
                 // We consider responses that hit the edge cache to be fresh, so we set `stale` to
                 // false. Otherwise the client would get caught in an infinite retry loop.
                 let response_for_edge_cache_result = worker::Response::try_from(
@@ -190,7 +287,14 @@ macro_rules! get_data {
             match cached_value {
                 Some(cached_value) => {
                     let to_body_for_cache = to_body.clone();
-                    let body = to_body(cached_value);
+                    let (cached_value, cached_at, fresh) = cached_value;
+                    let body = match cached_value {
+                        CachedBody::Value(value) => CachedBody::Value(DataResponseEnvelope {
+                            stale: !fresh,
+                            value: to_body(value),
+                        }),
+                        CachedBody::Precompressed(body) => CachedBody::Precompressed(body),
+                    };
 
                     let refresh_key = format!("{}:{}", self.env_name, $cache_key);
                     let already_refreshing = {
@@ -199,11 +303,53 @@ macro_rules! get_data {
                     };
 
                     if !already_refreshing {
+                        let leases_for_refresh = self.leases.clone();
+                        let env_name_for_lease = self.env_name.clone();
+                        let colo_for_lease = self.colo.clone();
+
                         self.ctx.wait_until(async move {
-                            if let Some(latest_value) = upstream_request.await {
+                            // The isolate-local set only deduplicates refreshes within this
+                            // isolate, so also take a lease that other isolates in this datacenter
+                            // can see. If we don't know the datacenter or the lease can't be
+                            // taken, we fall back to the isolate-local set alone.
+                            let lease_colo = match &colo_for_lease {
+                                Some(colo) => match lease::acquire(
+                                    &leases_for_refresh,
+                                    &env_name_for_lease,
+                                    colo,
+                                    $cache_key,
+                                    REFRESH_LEASE_TTL,
+                                )
+                                .await
+                                {
+                                    Ok(true) => Some(colo),
+                                    Ok(false) => {
+                                        console_log!(
+                                            "Skipping background refresh for {} (leased by another isolate).",
+                                            $cache_key,
+                                        );
+                                        inflight_refreshes().lock().unwrap().remove(&refresh_key);
+                                        return;
+                                    }
+                                    Err(e) => {
+                                        console_warn!("Failed taking refresh lease for {}: {}", $cache_key, e);
+                                        None
+                                    }
+                                },
+                                None => None,
+                            };
+
+                            if let Some((latest_value, started_at)) = upstream_request.await {
                                 let latest_body = to_body_for_cache(latest_value.clone());
-                                put_cache(latest_value, latest_body).await;
+                                put_cache(latest_value, latest_body, started_at).await;
+                            }
+
+                            if let Some(colo) = lease_colo {
+                                if let Err(e) = lease::release(&leases_for_refresh, &env_name_for_lease, colo, $cache_key).await {
+                                    console_warn!("Failed releasing refresh lease for {}: {}", $cache_key, e);
+                                }
                             }
+
                             inflight_refreshes().lock().unwrap().remove(&refresh_key);
                         });
                     } else {
@@ -213,29 +359,36 @@ macro_rules! get_data {
                         );
                     }
 
-                    Ok(EtagJson(DataResponseEnvelope {
-                        stale: true,
-                        value: body,
+                    Ok(CachedResponse {
+                        body,
+                        source: if fresh {
+                            CacheSource::PersistentFresh
+                        } else {
+                            CacheSource::PersistentStale
+                        },
+                        cached_at,
                     })
-                    .into_response())
                 },
                 None => {
                     // The persistent cache is empty, which should only be the case for new
                     // environments or after the cache is manually cleared. We need to block and
                     // wait for the upstream request.
-                    if let Some(latest_value) = upstream_request.await {
+                    if let Some((latest_value, started_at)) = upstream_request.await {
                         let body = to_body(latest_value.clone());
                         let body_for_cache = body.clone();
 
                         self.ctx.wait_until(async move {
-                            put_cache(latest_value, body_for_cache).await;
+                            put_cache(latest_value, body_for_cache, started_at).await;
                         });
 
-                        Ok(EtagJson(DataResponseEnvelope {
-                            stale: true,
-                            value: body,
+                        Ok(CachedResponse {
+                            body: CachedBody::Value(DataResponseEnvelope {
+                                stale: true,
+                                value: body,
+                            }),
+                            source: CacheSource::Upstream,
+                            cached_at: Some(started_at),
                         })
-                        .into_response())
                     } else {
                         Err(Error::NocoUnavailable)
                     }
@@ -245,67 +398,399 @@ macro_rules! get_data {
     }
 }
 
-impl Store {
-    pub async fn from_env_name(state: &AppState, env_name: EnvName) -> Result<Self, Error> {
-        let kv = state.kv.clone();
-        let ctx = Arc::clone(&state.ctx);
+// Whether a persistent cache entry is new enough to serve as fresh, so the client doesn't retry.
+// The scheduled worker replaces entries before they get older than `fresh_interval`, so attendees
+// don't get stale data after the edge cache expires.
+fn is_recently_cached(cached_at: Option<DateTime<Utc>>, fresh_interval: Duration) -> bool {
+    cached_at
+        .and_then(|cached_at| (Utc::now() - cached_at).to_std().ok())
+        .is_some_and(|age| age < fresh_interval)
+}
 
-        let api_token = kv::get_api_token(&kv, &env_name)
-            .await
-            .map_err(Error::Internal)?
-            .ok_or(Error::NoApiToken)?;
+// A response body from the cache, either as a value or already serialized and compressed.
+enum CachedBody<T> {
+    Value(T),
+    Precompressed(Vec<u8>),
+}
+
+// A response body, and which cache it came from.
+struct CachedResponse<T> {
+    body: CachedBody<T>,
+    source: CacheSource,
+    cached_at: Option<DateTime<Utc>>,
+}
+
+// The tables NocoDB sends change hooks for, as they appear in the receiver route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookTable {
+    Events,
+    People,
+    Tags,
+    About,
+    Links,
+    Pages,
+}
 
-        let env_config = kv::get_env_config(&kv, &env_name)
+// Save the report of which rows we skipped building `dataset`, and hand back the data. We save the
+// report even when it's empty, so fixed rows drop off it. Failing to save it isn't worth failing
+// the refresh over.
+async fn record_validation<T>(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+    validated: noco::Validated<T>,
+) -> T {
+    if !validated.issues.is_empty() {
+        console_warn!(
+            "Skipped {} invalid rows building {} for {}.",
+            validated.issues.len(),
+            dataset,
+            env_name,
+        );
+    }
+
+    let report = noco::ValidationReport {
+        dataset: dataset.to_string(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        issues: validated.issues,
+    };
+
+    if let Err(e) = kv::put_validation_report(kv, env_name, &report).await {
+        console_warn!("Failed saving the validation report for {}: {}", dataset, e);
+    }
+
+    validated.value
+}
+
+// Update how refreshing `dataset` last went, for the cache report. Failing to save it isn't worth
+// failing the refresh over.
+async fn update_cache_status(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+    update: impl FnOnce(&mut CacheStatus),
+) {
+    let result = async {
+        let mut status = kv::get_cache_status(kv, env_name, dataset)
+            .await?
+            .unwrap_or_else(|| CacheStatus {
+                dataset: dataset.to_string(),
+                ..Default::default()
+            });
+
+        update(&mut status);
+
+        kv::put_cache_status(kv, env_name, &status).await
+    };
+
+    if let Err(e) = result.await {
+        console_warn!("Failed saving the cache status for {}: {}", dataset, e);
+    }
+}
+
+async fn record_refresh_failure(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+    error: &dyn fmt::Display,
+) {
+    update_cache_status(kv, env_name, dataset, |status| {
+        status.last_failure = Some(RefreshFailure {
+            failed_at: Utc::now().to_rfc3339(),
+            error: error.to_string(),
+        });
+    })
+    .await;
+}
+
+// Put a dataset we just fetched from NocoDB in the persistent cache, and record how the refresh
+// went. `put` returns the size of what it stored.
+async fn cache_refreshed(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+    started_at: DateTime<Utc>,
+    put: impl Future<Output = anyhow::Result<usize>>,
+) -> anyhow::Result<()> {
+    match put.await {
+        Ok(size) => {
+            let finished_at = Utc::now();
+
+            update_cache_status(kv, env_name, dataset, |status| {
+                status.last_refreshed_at = Some(finished_at.to_rfc3339());
+                status.refresh_duration_ms =
+                    u64::try_from((finished_at - started_at).num_milliseconds()).ok();
+                status.size_bytes = u64::try_from(size).ok();
+            })
+            .await;
+
+            Ok(())
+        }
+        Err(e) => {
+            record_refresh_failure(kv, env_name, dataset, &e).await;
+            Err(e)
+        }
+    }
+}
+
+// Fetch data from NocoDB to refresh `datasets`, recording the failure against each of them if it
+// fails.
+async fn fetch_for_refresh<T>(
+    kv: &KvStore,
+    env_name: &EnvName,
+    datasets: &[&str],
+    fetch: impl Future<Output = anyhow::Result<T>>,
+) -> Result<T, Error> {
+    match fetch.await {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            for dataset in datasets {
+                record_refresh_failure(kv, env_name, dataset, &e).await;
+            }
+
+            Err(Error::Internal(e))
+        }
+    }
+}
+
+// Fetch one dataset from NocoDB and put it in the persistent cache, recording how it went. This
+// returns the dataset if it was cached.
+async fn warm_dataset<T>(
+    kv: &KvStore,
+    env_name: &EnvName,
+    dataset: &str,
+    fetch: impl Future<Output = anyhow::Result<noco::Validated<T>>>,
+    put: impl AsyncFnOnce(&T) -> anyhow::Result<usize>,
+) -> Option<T> {
+    let started_at = Utc::now();
+
+    let value = match fetch_for_refresh(kv, env_name, &[dataset], fetch).await {
+        Ok(validated) => record_validation(kv, env_name, dataset, validated).await,
+        Err(e) => {
+            console_warn!("Failed getting {} from NocoDB: {}", dataset, e);
+            return None;
+        }
+    };
+
+    match cache_refreshed(kv, env_name, dataset, started_at, put(&value)).await {
+        Ok(()) => Some(value),
+        Err(e) => {
+            console_warn!("Failed putting {} in KV cache: {}", dataset, e);
+            None
+        }
+    }
+}
+
+// Cache the events along with a snapshot of this version of them, so clients that have it can
+// sync just what changes next. The snapshot's expiration is reset every time we cache the version
+// again, so it only expires once the version is old. Failing to save it only costs clients a full
+// sync.
+async fn put_cached_events(
+    kv: &KvStore,
+    env_name: &EnvName,
+    events: &[noco::Event],
+) -> anyhow::Result<usize> {
+    let size = kv::put_cached_events(kv, env_name, events).await?;
+
+    let version = changes::events_version(events);
+    let snapshot = changes::events_snapshot(events);
+
+    if let Err(e) = kv::put_events_snapshot(
+        kv,
+        env_name,
+        &version,
+        &snapshot,
+        changes::EVENTS_SNAPSHOT_TTL,
+    )
+    .await
+    {
+        console_warn!("Failed saving the events snapshot for {}: {}", env_name, e);
+    }
+
+    Ok(size)
+}
+
+// What we need to talk to an environment's NocoDB base.
+struct NocoConnection {
+    noco_client: NocoClient,
+    base_id: BaseId,
+    env_config: Config,
+}
+
+async fn connect_noco(kv: &KvStore, env_name: &EnvName) -> Result<NocoConnection, Error> {
+    let api_token = kv::get_api_token(kv, env_name)
+        .await
+        .map_err(Error::Internal)?
+        .ok_or(Error::NoApiToken)?;
+
+    let env_config = kv::get_env_config(kv, env_name)
+        .await
+        .map_err(Error::Internal)?;
+
+    // The Postgres database is the source of truth for the base ID, but we cache it in KV to
+    // avoid needing to open a separate Postgres connection per request. Otherwise, heavy load
+    // would exhaust the connection pool and cause this worker to start returning 500 errors.
+    let base_id = match kv::get_base_id(kv, env_name)
+        .await
+        .map_err(Error::Internal)?
+    {
+        Some(base_id) => base_id,
+        None => {
+            let db_client = DbClient::connect(
+                &Option::<DbConnectionConfig>::from(env_config.clone())
+                    .ok_or(Error::MissingEnvConfig)?,
+            )
             .await
             .map_err(Error::Internal)?;
 
-        // The Postgres database is the source of truth for the base ID, but we cache it in KV to
-        // avoid needing to open a separate Postgres connection per request. Otherwise, heavy load
-        // would exhaust the connection pool and cause this worker to start returning 500 errors.
-        let base_id = match kv::get_base_id(&kv, &env_name)
-            .await
-            .map_err(Error::Internal)?
-        {
-            Some(base_id) => base_id,
-            None => {
-                let db_client = DbClient::connect(
-                    &Option::<DbConnectionConfig>::from(env_config.clone())
-                        .ok_or(Error::MissingEnvConfig)?,
-                )
+            let base_id = db_client
+                .get_base()
                 .await
-                .map_err(Error::Internal)?;
+                .map_err(Error::Internal)?
+                .ok_or(Error::NoBaseId)?;
 
-                let base_id = db_client
-                    .get_base()
-                    .await
-                    .map_err(Error::Internal)?
-                    .ok_or(Error::NoBaseId)?;
+            if let Err(e) = kv::put_base_id(kv, env_name, &base_id).await {
+                // This need not be a fatal error.
+                console_warn!("Failed to cache base_id in KV: {}", e);
+            }
 
-                if let Err(e) = kv::put_base_id(&kv, &env_name, &base_id).await {
-                    // This need not be a fatal error.
-                    console_warn!("Failed to cache base_id in KV: {}", e);
-                }
+            base_id
+        }
+    };
 
-                base_id
-            }
-        };
+    let dash_origin = url::dash_origin(env_name).map_err(Error::Internal)?;
+
+    Ok(NocoConnection {
+        noco_client: NocoClient::new(dash_origin, api_token),
+        base_id,
+        env_config,
+    })
+}
 
-        let dash_origin = url::dash_origin(&env_name).map_err(Error::Internal)?;
+impl Store {
+    pub async fn from_env_name(state: &AppState, env_name: EnvName) -> Result<Self, Error> {
+        let kv = state.kv.clone();
+        let ctx = Arc::clone(&state.ctx);
 
-        let noco_client = NocoClient::new(dash_origin.clone(), api_token);
-        let neon_client = NeonClient::new();
+        let NocoConnection {
+            noco_client,
+            base_id,
+            env_config,
+        } = connect_noco(&kv, &env_name).await?;
 
         Ok(Self {
             noco_client,
-            neon_client,
+            neon_client: NeonClient::new(),
             kv,
             ctx,
             env_name,
             base_id,
             env_config,
+            colo: state.colo.clone(),
+            live: state.live.clone(),
+            leases: state.leases.clone(),
         })
     }
 
+    // Refresh every dataset in the persistent cache from NocoDB, so requests that miss the edge
+    // cache after a quiet period don't find it empty. This runs outside of any request, so it
+    // doesn't need a `Store`.
+    #[worker::send]
+    pub async fn warm_cache(
+        kv: &KvStore,
+        live: &ObjectNamespace,
+        env_name: &EnvName,
+    ) -> Result<(), Error> {
+        let NocoConnection {
+            noco_client,
+            base_id,
+            ..
+        } = connect_noco(kv, env_name).await?;
+
+        let table_ids = Self::get_table_ids(kv, env_name, &noco_client, &base_id).await?;
+
+        // Each dataset is refreshed on its own, so one that fails doesn't stop the others.
+        let (events, info, pages, announcements, files) = futures::join!(
+            warm_dataset(
+                kv,
+                env_name,
+                "events",
+                noco::get_events(&noco_client, &table_ids),
+                async |events| put_cached_events(kv, env_name, events).await,
+            ),
+            warm_dataset(
+                kv,
+                env_name,
+                "info",
+                noco::get_info(&noco_client, &table_ids),
+                async |info| kv::put_cached_info(kv, env_name, info).await,
+            ),
+            warm_dataset(
+                kv,
+                env_name,
+                "pages",
+                noco::get_pages(&noco_client, &table_ids),
+                async |pages| kv::put_cached_pages(kv, env_name, pages).await,
+            ),
+            warm_dataset(
+                kv,
+                env_name,
+                "announcements",
+                noco::get_announcements(&noco_client, &table_ids),
+                async |announcements| {
+                    kv::put_cached_announcements(kv, env_name, announcements).await
+                },
+            ),
+            warm_dataset(
+                kv,
+                env_name,
+                "files",
+                noco::get_files(&noco_client, &table_ids),
+                async |files| kv::put_cached_files(kv, env_name, files).await,
+            ),
+        );
+
+        let messages = [
+            events
+                .as_ref()
+                .map(|events| LiveMessage::of(env_name, events)),
+            info.as_ref().map(|info| LiveMessage::of(env_name, info)),
+            pages.as_ref().map(|pages| LiveMessage::of(env_name, pages)),
+            announcements
+                .as_ref()
+                .map(|announcements| LiveMessage::of(env_name, announcements)),
+            files.as_ref().map(|files| LiveMessage::of(env_name, files)),
+        ];
+
+        live::notify(
+            live,
+            env_name,
+            &messages.into_iter().flatten().collect::<Vec<_>>(),
+        )
+        .await;
+
+        let failed = [
+            ("events", events.is_none()),
+            ("info", info.is_none()),
+            ("pages", pages.is_none()),
+            ("announcements", announcements.is_none()),
+            ("files", files.is_none()),
+        ]
+        .into_iter()
+        .filter_map(|(dataset, failed)| failed.then_some(dataset))
+        .collect::<Vec<_>>();
+
+        if !failed.is_empty() {
+            return Err(Error::Internal(anyhow::anyhow!(
+                "Failed to warm {}.",
+                failed.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
     async fn connect_db(&self) -> Result<DbClient, Error> {
         DbClient::connect(
             &Option::<DbConnectionConfig>::from(self.env_config.clone())
@@ -328,7 +813,16 @@ impl Store {
         &self.env_name
     }
 
-    fn cache_ttl(&self) -> Duration {
+    // The con's timezone, if it has one. The config is validated when it's written, so this should
+    // always parse.
+    pub fn timezone(&self) -> Option<Tz> {
+        self.env_config
+            .timezone
+            .as_deref()
+            .and_then(|timezone| timezone.parse().ok())
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
         self.env_config
             .cache_ttl
             .map(Duration::from_millis)
@@ -366,15 +860,19 @@ impl Store {
 
     get_data! {
         fn_name: get_events,
+        value_fn_name: get_events_value,
+        body_fn_name: get_events_body,
         type_name: Vec<noco::Event>,
         get_api_fn: noco::get_events,
         get_cached_fn: kv::get_cached_events,
-        put_cached_fn: kv::put_cached_events,
+        put_cached_fn: put_cached_events,
         cache_key: "events",
     }
 
     get_data! {
         fn_name: get_info,
+        value_fn_name: get_info_value,
+        body_fn_name: get_info_body,
         type_name: noco::Info,
         get_api_fn: noco::get_info,
         get_cached_fn: kv::get_cached_info,
@@ -384,6 +882,8 @@ impl Store {
 
     get_data! {
         fn_name: get_pages,
+        value_fn_name: get_pages_value,
+        body_fn_name: get_pages_body,
         type_name: Vec<noco::Page>,
         get_api_fn: noco::get_pages,
         get_cached_fn: kv::get_cached_pages,
@@ -393,6 +893,8 @@ impl Store {
 
     get_data! {
         fn_name: get_announcements,
+        value_fn_name: get_announcements_value,
+        body_fn_name: get_announcements_body,
         type_name: Vec<noco::Announcement>,
         get_api_fn: noco::get_announcements,
         get_cached_fn: kv::get_cached_announcements,
@@ -402,6 +904,8 @@ impl Store {
 
     get_data! {
         fn_name: get_files,
+        value_fn_name: get_files_value,
+        body_fn_name: get_files_body,
         type_name: Vec<noco::File>,
         get_api_fn: noco::get_files,
         get_cached_fn: kv::get_cached_files,
@@ -410,31 +914,207 @@ impl Store {
     }
 
     // Refresh the cache specifically with the latest announcements from NocoDB. This is necessary
-    // because we send out push notifications for announcements.
+    // because we send out push notifications for announcements. This returns the announcements we
+    // just cached, so callers can tell whether a given announcement is available to clients yet.
     #[worker::send]
-    pub async fn refresh_announcements_cache(&self) -> Result<(), Error> {
+    pub async fn refresh_announcements_cache(&self) -> Result<Vec<noco::Announcement>, Error> {
+        let started_at = Utc::now();
         let table_ids =
             Self::get_table_ids(&self.kv, &self.env_name, &self.noco_client, &self.base_id).await?;
 
-        let announcements = noco::get_announcements(&self.noco_client, &table_ids)
-            .await
-            .map_err(Error::Internal)?;
+        let announcements = fetch_for_refresh(
+            &self.kv,
+            &self.env_name,
+            &["announcements"],
+            noco::get_announcements(&self.noco_client, &table_ids),
+        )
+        .await?;
+        let announcements =
+            record_validation(&self.kv, &self.env_name, "announcements", announcements).await;
 
         // Refresh the persistent cache.
-        kv::put_cached_announcements(&self.kv, &self.env_name, &announcements)
+        cache_refreshed(
+            &self.kv,
+            &self.env_name,
+            "announcements",
+            started_at,
+            kv::put_cached_announcements(&self.kv, &self.env_name, &announcements),
+        )
+        .await
+        .map_err(Error::Internal)?;
+
+        self.purge_edge_cache().await?;
+
+        live::notify(
+            &self.live,
+            &self.env_name,
+            &[LiveMessage::of(&self.env_name, &announcements)],
+        )
+        .await;
+
+        Ok(announcements)
+    }
+
+    // Refresh just the cached data that's built from `table`, after NocoDB tells us it changed.
+    #[worker::send]
+    pub async fn refresh_table_cache(&self, table: HookTable) -> Result<(), Error> {
+        let started_at = Utc::now();
+        let table_ids =
+            Self::get_table_ids(&self.kv, &self.env_name, &self.noco_client, &self.base_id).await?;
+
+        let kv = &self.kv;
+        let env_name = &self.env_name;
+        let noco_client = &self.noco_client;
+
+        let messages = match table {
+            // Events include the names of their people and tags.
+            HookTable::Events | HookTable::People | HookTable::Tags => {
+                let events = fetch_for_refresh(
+                    kv,
+                    env_name,
+                    &["events"],
+                    noco::get_events(noco_client, &table_ids),
+                )
+                .await?;
+                let events = record_validation(kv, env_name, "events", events).await;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "events",
+                    started_at,
+                    put_cached_events(kv, env_name, &events),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                vec![LiveMessage::of(env_name, &events)]
+            }
+            HookTable::Links => {
+                let info = fetch_for_refresh(
+                    kv,
+                    env_name,
+                    &["info"],
+                    noco::get_info(noco_client, &table_ids),
+                )
+                .await?;
+                let info = record_validation(kv, env_name, "info", info).await;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "info",
+                    started_at,
+                    kv::put_cached_info(kv, env_name, &info),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                vec![LiveMessage::of(env_name, &info)]
+            }
+            // The files list includes files attached to the about page and to pages.
+            HookTable::About => {
+                let (info, files) = fetch_for_refresh(kv, env_name, &["info", "files"], async {
+                    futures::try_join!(
+                        noco::get_info(noco_client, &table_ids),
+                        noco::get_files(noco_client, &table_ids),
+                    )
+                })
+                .await?;
+                let info = record_validation(kv, env_name, "info", info).await;
+                let files = record_validation(kv, env_name, "files", files).await;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "info",
+                    started_at,
+                    kv::put_cached_info(kv, env_name, &info),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "files",
+                    started_at,
+                    kv::put_cached_files(kv, env_name, &files),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                vec![
+                    LiveMessage::of(env_name, &info),
+                    LiveMessage::of(env_name, &files),
+                ]
+            }
+            HookTable::Pages => {
+                let (pages, files) = fetch_for_refresh(kv, env_name, &["pages", "files"], async {
+                    futures::try_join!(
+                        noco::get_pages(noco_client, &table_ids),
+                        noco::get_files(noco_client, &table_ids),
+                    )
+                })
+                .await?;
+                let pages = record_validation(kv, env_name, "pages", pages).await;
+                let files = record_validation(kv, env_name, "files", files).await;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "pages",
+                    started_at,
+                    kv::put_cached_pages(kv, env_name, &pages),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                cache_refreshed(
+                    kv,
+                    env_name,
+                    "files",
+                    started_at,
+                    kv::put_cached_files(kv, env_name, &files),
+                )
+                .await
+                .map_err(Error::Internal)?;
+                vec![
+                    LiveMessage::of(env_name, &pages),
+                    LiveMessage::of(env_name, &files),
+                ]
+            }
+        };
+
+        self.purge_edge_cache().await?;
+
+        live::notify(&self.live, &self.env_name, &messages).await;
+
+        Ok(())
+    }
+
+    // Check the schedule in NocoDB for events we leave out of it and for likely mistakes.
+    pub async fn lint_schedule(&self) -> Result<Vec<noco::LintIssue>, Error> {
+        let table_ids =
+            Self::get_table_ids(&self.kv, &self.env_name, &self.noco_client, &self.base_id).await?;
+
+        // The config is validated when it's written, so these should always parse.
+        let parse_date = |date: &Option<String>| {
+            date.as_deref()
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        };
+
+        let options = noco::LintOptions {
+            con_start_date: parse_date(&self.env_config.con_start_date),
+            con_end_date: parse_date(&self.env_config.con_end_date),
+            timezone: self.timezone(),
+        };
+
+        noco::lint_schedule(&self.noco_client, &table_ids, &options)
             .await
-            .map_err(Error::Internal)?;
+            .map_err(Error::Internal)
+    }
 
-        // Purge the edge cache for this environment so incoming requests hit the persistent cache.
+    // Purge the edge cache for this environment so incoming requests hit the persistent cache.
+    async fn purge_edge_cache(&self) -> Result<(), Error> {
         cf::Client::new()
             .purge_cache(
                 &config::cloudflare_zone_id(),
                 &cf::CacheTag::for_env(&self.env_name),
             )
             .await
-            .map_err(Error::Internal)?;
-
-        Ok(())
+            .map_err(Error::Internal)
     }
 
     #[worker::send]
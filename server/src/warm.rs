@@ -0,0 +1,168 @@
+//! Scheduled warming of the persistent cache, so the first attendee after a quiet period doesn't
+//! wait on NocoDB or get a 503 because the cache is empty.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Timelike, Utc};
+use worker::{ObjectNamespace, console_error, console_warn, kv::KvStore};
+
+use crate::env::EnvName;
+use crate::kv;
+use crate::noco;
+use crate::store::Store;
+
+/// The cron schedule in `wrangler.toml` that runs [`warm_caches`]. This must be at least as often
+/// as [`EVENT_WINDOW_INTERVAL`].
+pub const WARM_CRON: &str = "*/5 * * * *";
+
+/// How often we warm an environment's cache while the con is on.
+const EVENT_WINDOW_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How often we warm it the rest of the time.
+const QUIET_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How much longer than the warm interval a persistent cache entry stays fresh, so cron jitter or a
+/// slow warm run doesn't mark entries stale just before the next run replaces them.
+const FRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// How long before the first event and after the last that we consider the con to be on. People
+/// check the schedule on the way there and look things up afterwards.
+const EVENT_WINDOW_MARGIN: TimeDelta = TimeDelta::days(1);
+
+/// The first and last times in the schedule, if there are any events.
+fn event_window(events: &[noco::Event]) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |time: &str| {
+        DateTime::parse_from_rfc3339(time)
+            .ok()
+            .map(|time| time.to_utc())
+    };
+
+    let starts = events.iter().filter_map(|event| parse(&event.start_time));
+    let ends = events
+        .iter()
+        .filter_map(|event| parse(event.end_time.as_deref().unwrap_or(&event.start_time)));
+
+    Some((starts.min()?, ends.max()?))
+}
+
+/// How often to warm an environment, given the window its events span.
+fn warm_interval(window: Option<(DateTime<Utc>, DateTime<Utc>)>, now: DateTime<Utc>) -> Duration {
+    match window {
+        Some((start, end))
+            if now >= start - EVENT_WINDOW_MARGIN && now <= end + EVENT_WINDOW_MARGIN =>
+        {
+            EVENT_WINDOW_INTERVAL
+        }
+        _ => QUIET_INTERVAL,
+    }
+}
+
+/// How old a persistent cache entry can be and still be served as fresh, given how often we're
+/// warming its environment. Environments we haven't warmed yet get the shortest interval.
+pub fn fresh_interval(warm_interval: Option<Duration>) -> Duration {
+    warm_interval.unwrap_or(EVENT_WINDOW_INTERVAL) + FRESH_MARGIN
+}
+
+/// Whether the cron run at `scheduled_at` is one where we should warm an environment that wants
+/// warming every `interval`. Every run is aligned to the interval, so this is stateless.
+fn is_due(interval: Duration, scheduled_at: DateTime<Utc>) -> bool {
+    let minute_of_day = u64::from(scheduled_at.hour() * 60 + scheduled_at.minute());
+    let interval_minutes = (interval.as_secs() / 60).max(1);
+    minute_of_day % interval_minutes == 0
+}
+
+async fn warm_env(
+    kv: &KvStore,
+    live: &ObjectNamespace,
+    env_name: &EnvName,
+    scheduled_at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    // We decide from the events we already have cached. An environment with nothing cached yet is
+    // warmed on the quiet schedule until its first events are.
+    let events = kv::get_cached_events(kv, env_name)
+        .await?
+        .map(|entry| entry.value())
+        .transpose()?
+        .unwrap_or_default();
+
+    let interval = warm_interval(event_window(&events), scheduled_at);
+
+    // Reads use this to decide how long cached values stay fresh. It only changes when the con
+    // starts or ends, so we only write it then.
+    if kv::get_warm_interval(kv, env_name).await.ok().flatten() != Some(interval)
+        && let Err(err) = kv::put_warm_interval(kv, env_name, interval).await
+    {
+        console_warn!("Failed to save the warm interval for {env_name}: {err}");
+    }
+
+    if !is_due(interval, scheduled_at) {
+        return Ok(());
+    }
+
+    Store::warm_cache(kv, live, env_name)
+        .await
+        .map_err(anyhow::Error::from)
+}
+
+pub async fn warm_caches(
+    kv: &KvStore,
+    live: &ObjectNamespace,
+    scheduled_at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    // One environment failing shouldn't stop the others from being warmed.
+    for env_name in kv::list_env_names(kv).await? {
+        if let Err(err) = warm_env(kv, live, &env_name, scheduled_at).await {
+            console_error!("Failed to warm the cache for {env_name}: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        "2026-07-04T00:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_hour(hour)
+            .unwrap()
+            .with_minute(minute)
+            .unwrap()
+    }
+
+    #[test]
+    fn warms_often_during_the_con_and_rarely_otherwise() {
+        let window = Some((at(10, 0), at(22, 0)));
+
+        assert_eq!(warm_interval(window, at(12, 0)), EVENT_WINDOW_INTERVAL);
+        assert_eq!(
+            warm_interval(window, at(12, 0) - TimeDelta::days(3)),
+            QUIET_INTERVAL
+        );
+        assert_eq!(
+            warm_interval(window, at(12, 0) + TimeDelta::hours(12)),
+            EVENT_WINDOW_INTERVAL
+        );
+        assert_eq!(warm_interval(None, at(12, 0)), QUIET_INTERVAL);
+    }
+
+    #[test]
+    fn entries_stay_fresh_a_little_past_the_warm_interval() {
+        assert!(fresh_interval(Some(EVENT_WINDOW_INTERVAL)) > EVENT_WINDOW_INTERVAL);
+        assert!(fresh_interval(Some(QUIET_INTERVAL)) > QUIET_INTERVAL);
+        assert_eq!(
+            fresh_interval(None),
+            fresh_interval(Some(EVENT_WINDOW_INTERVAL))
+        );
+    }
+
+    #[test]
+    fn runs_are_due_on_interval_boundaries() {
+        assert!(is_due(EVENT_WINDOW_INTERVAL, at(12, 35)));
+        assert!(is_due(QUIET_INTERVAL, at(12, 0)));
+        assert!(!is_due(QUIET_INTERVAL, at(12, 35)));
+    }
+}